@@ -1,18 +1,64 @@
-use crate::AbstractValue;
+use std::hash::{Hash, Hasher};
 
-#[derive(Clone)]
+use crate::type_expr::TypeExpr;
+
+// 1-based position of a node in the source
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+// A literal as written in the program, which each domain gives its own value
+// (see `ValueDomain::literal`). String literals are `ASTNode::StringLiteral`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Literal {
+    Number(f64),
+    Boolean(bool),
+    Null,
+    Undefined,
+}
+
+// the lexer never reads a `NaN`, so every literal equals itself
+impl Eq for Literal {}
+
+impl Hash for Literal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            // `-0 == 0`
+            Literal::Number(n) => (n + 0.0).to_bits().hash(state),
+            Literal::Boolean(b) => b.hash(state),
+            Literal::Null | Literal::Undefined => {}
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ASTNode {
-    Literal(AbstractValue),
+    Literal(Literal),
+    // a string literal, kept so conditions like `typeof x === "number"` can be read
+    StringLiteral(String),
     Variable(String),
     Assignment {
         target: String,
         value: Box<ASTNode>,
     },
+    // `let x = value;`, also for `var` and `const`
+    Declaration {
+        target: String,
+        value: Box<ASTNode>,
+    },
     BinaryOp {
         op: String,
         left: Box<ASTNode>,
         right: Box<ASTNode>,
     },
+    // `!x`, `-x` and `typeof x`
+    UnaryOp {
+        op: String,
+        operand: Box<ASTNode>,
+    },
     IfStatement {
         condition: Box<ASTNode>,
         then_branch: Box<ASTNode>,
@@ -25,12 +71,25 @@ pub enum ASTNode {
     Block {
         statements: Vec<ASTNode>,
     },
+    // parameters and generics carry their annotation and constraint, if any
+    // (`a: T[]`, `T extends Number`), like the return type (`): T`)
     FunctionDeclaration {
         name: String,
-        params: Vec<String>,
-        generics: Vec<(String, Option<String>)>,
+        params: Vec<(String, Option<TypeExpr>)>,
+        generics: Vec<(String, Option<TypeExpr>)>,
+        return_type: Option<TypeExpr>,
+        body: Box<ASTNode>,
+    },
+    // `function (a, b) { ... }` in expression position, the name is optional
+    FunctionExpression {
+        name: Option<String>,
+        params: Vec<(String, Option<TypeExpr>)>,
+        generics: Vec<(String, Option<TypeExpr>)>,
+        return_type: Option<TypeExpr>,
         body: Box<ASTNode>,
     },
+    // `return value;` or a bare `return;`
+    Return(Option<Box<ASTNode>>),
     FunctionCall {
         function: Box<ASTNode>,
         arguments: Vec<ASTNode>,
@@ -40,4 +99,22 @@ pub enum ASTNode {
         array: Box<ASTNode>,
         index: Box<ASTNode>,
     },
+    ObjectLiteral(Vec<(String, ASTNode)>),
+    // `o.x` or `o["x"]`
+    PropertyAccess {
+        object: Box<ASTNode>,
+        property: String,
+    },
+    // `o.x = value`
+    PropertyAssignment {
+        object: Box<ASTNode>,
+        property: String,
+        value: Box<ASTNode>,
+    },
+    // a node annotated with where it appears in the source.
+    // the parser wraps every statement so diagnostics can point back to it.
+    Located {
+        location: Location,
+        node: Box<ASTNode>,
+    },
 }
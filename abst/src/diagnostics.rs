@@ -0,0 +1,92 @@
+use std::fmt;
+
+use crate::ast::Location;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiagnosticKind {
+    // a loop did not reach a fixpoint within the iteration cap
+    LoopIterationLimit,
+    // a statement or loop body that is never executed
+    UnreachableCode,
+    // a recursive call did not reach a fixpoint, or calls were nested too deep
+    RecursionLimit,
+    // reading a property the object does not (definitely) have
+    MissingProperty,
+    // property access or assignment on a value that is not an object
+    NotAnObject,
+    // reading a variable that has not been assigned
+    UnknownVariable,
+    // `BinaryOp` with an operator the interpreter does not know
+    UnknownOperator,
+    // calling something that is not a declared function
+    UnknownFunction,
+    // calling a value that may not be a function
+    NotAFunction,
+    // indexing an array with a value that may not be a number
+    NonNumberIndex,
+    // indexing a value that is not an array
+    NotAnArray,
+    // an array index that may be outside the array
+    IndexOutOfBounds,
+    // an operand has a type the operator is not defined for
+    InvalidOperand,
+    // a generic argument does not satisfy its constraint
+    ConstraintViolation,
+    // a generic is inferred as different types from two arguments
+    GenericConflict,
+    // an argument or a returned value does not have the type it is annotated with
+    TypeMismatch,
+    // an annotation or constraint names a type that is neither known nor a generic
+    UnknownType,
+    // a return type names a generic that no parameter annotation binds
+    UnboundGeneric,
+}
+
+// a message produced while interpreting the program
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    pub severity: Severity,
+    pub message: String,
+    // the statement being interpreted, when the program came from source
+    pub location: Option<Location>,
+}
+
+impl Diagnostic {
+    pub fn warning(kind: DiagnosticKind, message: impl Into<String>) -> Self {
+        Diagnostic {
+            kind,
+            severity: Severity::Warning,
+            message: message.into(),
+            location: None,
+        }
+    }
+
+    pub fn error(kind: DiagnosticKind, message: impl Into<String>) -> Self {
+        Diagnostic {
+            kind,
+            severity: Severity::Error,
+            message: message.into(),
+            location: None,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(location) = self.location {
+            write!(f, "{}:{}: ", location.line, location.column)?;
+        }
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}: {}", severity, self.message)
+    }
+}
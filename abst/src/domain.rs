@@ -0,0 +1,161 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    hash::Hash,
+};
+
+use crate::{
+    ast::{ASTNode, Literal},
+    diagnostics::Diagnostic,
+    types::{AbstractState, Closure, Function},
+};
+
+// Abstract domains.
+//
+// An abstract domain is a lattice whose elements each describe a set of runtime
+// values. `leq` is its partial order (describing no more values than), `join`
+// and `meet` are the least upper and greatest lower bounds, and `bottom` (no
+// value) and `top` (any value) are its least and greatest elements.
+//
+// Loops and recursive calls are analyzed with `widen`, which over-approximates
+// both of its operands so that an increasing chain of values stabilizes, and
+// `narrow`, which refines a widened value towards a more precise one without
+// going below it.
+//
+// `interpret` evaluates programs in any `ValueDomain`: the type tags of
+// `AbstractValue`, or a simpler domain like `Sign`.
+
+pub trait AbstractDomain: Sized {
+    fn bottom() -> Self;
+    fn top() -> Self;
+    fn join(&self, other: &Self) -> Self;
+    fn meet(&self, other: &Self) -> Self;
+    // `self` from the previous iteration, widened with `next`
+    fn widen(&self, next: &Self) -> Self;
+    // `self`, a widened value, narrowed with `next`, a more precise result of
+    // the same iteration
+    fn narrow(&self, next: &Self) -> Self;
+    fn leq(&self, other: &Self) -> bool;
+}
+
+// the generics bound for a call, by name
+pub type Generics<V> = HashMap<String, Box<V>>;
+
+// what calling a value may call
+pub struct Callees<V> {
+    pub closures: Vec<Closure<V>>,
+    // whether it may be a function nothing is known about (e.g. `top`)
+    pub unknown: bool,
+    // what it may be that cannot be called
+    pub not_callable: Vec<V>,
+}
+
+// How the values of a domain evaluate.
+//
+// The interpreter takes care of variables, control flow and calls; the domain
+// gives the values of literals and operators and what each branch of a
+// condition may assume. Domains that only describe some values, like numbers,
+// can leave out the rest: by default arrays, objects and functions are `top`,
+// and annotations are not checked.
+pub trait ValueDomain: AbstractDomain + Clone + fmt::Debug + Eq + Hash {
+    // the value of a literal of the program
+    fn literal(literal: &Literal) -> Self;
+    fn string_literal(text: &str) -> Self;
+    // the runtime `undefined`, e.g. the value of a statement
+    fn undefined() -> Self;
+    // `true` or `false` when `outcome` is known, any boolean otherwise
+    fn boolean(outcome: Option<bool>) -> Self;
+
+    // The type of `self`, forgetting what is known about the value (by default
+    // nothing is). Calls of a function whose arguments have the same types share
+    // the widening of their arguments, see `SummaryTable::key`.
+    fn base_type(&self) -> Self {
+        self.clone()
+    }
+
+    // `left op right`, `None` when the operator is unknown. An operator that is
+    // not defined for the operands gives `top`, which is reported.
+    fn binary(op: &str, left: &Self, right: &Self) -> Option<Self>;
+    // `op operand`, `None` when the operator is unknown
+    fn unary(op: &str, operand: &Self) -> Option<Self>;
+
+    // `Some(b)` when every runtime value described by `self` converts to `b`
+    // in a boolean context, `None` when it may be either
+    fn truthiness(&self) -> Option<bool>;
+
+    // the values of `self` that may be truthy (`None` if there are none)
+    fn truthy_part(&self) -> Option<Self> {
+        (self.truthiness() != Some(false)).then(|| self.clone())
+    }
+
+    // the values of `self` that may be falsy (`None` if there are none)
+    fn falsy_part(&self) -> Option<Self> {
+        (self.truthiness() != Some(true)).then(|| self.clone())
+    }
+
+    // narrow the variables of `state` assuming `condition` evaluates to `assume`
+    fn refine(_condition: &ASTNode, _assume: bool, _state: &mut AbstractState<Self>) {}
+
+    fn array(_elements: Vec<Self>) -> Self {
+        Self::top()
+    }
+
+    // `array[index]`
+    fn read_element(_array: &Self, _index: &Self, _state: &mut AbstractState<Self>) -> Self {
+        Self::top()
+    }
+
+    fn object(_props: BTreeMap<String, Self>) -> Self {
+        Self::top()
+    }
+
+    // `self.property`
+    fn read_property(&self, _property: &str, _state: &mut AbstractState<Self>) -> Self {
+        Self::top()
+    }
+
+    // `self` after `self.property = value`, `None` when it does not change
+    // (e.g. no object it may be can have the property set)
+    fn set_property(
+        &self,
+        _property: &str,
+        _value: Self,
+        _state: &mut AbstractState<Self>,
+    ) -> Option<Self> {
+        None
+    }
+
+    fn closure(_closure: Closure<Self>) -> Self {
+        Self::top()
+    }
+
+    fn callees(&self) -> Callees<Self> {
+        Callees {
+            closures: Vec::new(),
+            unknown: *self != Self::bottom(),
+            not_callable: Vec::new(),
+        }
+    }
+
+    // Bind the generics of a call of `func` named `name` from its arguments,
+    // checking them against the annotations. `Err` gives up on the call.
+    fn bind_generics(
+        _name: &str,
+        _func: &Function,
+        _arguments: &[Self],
+    ) -> Result<Generics<Self>, Diagnostic> {
+        Ok(Generics::new())
+    }
+
+    // the value of a call of `func` whose body returned `result`, reporting to
+    // `diagnostics` when it does not match the declared return type
+    fn check_return(
+        _name: &str,
+        _func: &Function,
+        result: Self,
+        _generics: &Generics<Self>,
+        _diagnostics: &mut Vec<Diagnostic>,
+    ) -> Self {
+        result
+    }
+}
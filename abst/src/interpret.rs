@@ -1,94 +1,277 @@
-use std::collections::HashMap;
-
-use crate::{ast::ASTNode, types::Function, AbstractState, AbstractValue};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    ast::{ASTNode, Literal, Location},
+    diagnostics::{Diagnostic, DiagnosticKind},
+    domain::{AbstractDomain, Callees, Generics, ValueDomain},
+    interval::Interval,
+    narrowing::{assume, is_array_is_array, refine},
+    summary::{CallSummary, SummaryStats, MAX_CALL_DEPTH, MAX_RECURSION_ITERATIONS},
+    type_expr::{TypeExpr, UnifyError},
+    types::{AbstractArray, AbstractObject, Closure, Function, Scope},
+    AbstractState, AbstractValue,
+};
 
 // This module performs abstract interpretation of an AST (Abstract Syntax Tree).
 //
 // The core functionality revolves around interpreting nodes using abstract values,
-// which represent sets of possible runtime values.
+// which represent sets of possible runtime values. `interpret` works in any
+// `ValueDomain` (see `domain.rs`); the type tags of `AbstractValue` are the one
+// `analyze` uses.
 //
-// A key aspect of this interpretation is the merging of abstract values,
+// A key aspect of this interpretation is the joining of abstract values,
 // especially when dealing with control flow constructs like if-statements and loops.
-// The `merge` operation combines two abstract values into one, representing the union
+// The `join` operation combines two abstract values into one, representing the union
 // of their possible values.
 //
-// The `merge` operation is designed using algebraic properties to ensure consistency
+// The `join` operation is designed using algebraic properties to ensure consistency
 // and correctness:
 //
-// - **Associativity**: `(a.merge(b)).merge(c) == a.merge(b.merge(c))`
-//   - The grouping of merge operations does not affect the final result.
-// - **Commutativity**: `a.merge(b) == b.merge(a)`
-//   - The order of operands does not affect the merge result.
-// - **Idempotence**: `a.merge(a) == a`
-//   - Merging a value with itself yields the same value.
-// - **Identity Element**: `Undefined` acts as the identity element.
-//   - `a.merge(Undefined) == a` and `Undefined.merge(a) == a`
+// - **Associativity**: `(a.join(b)).join(c) == a.join(b.join(c))`
+//   - The grouping of join operations does not affect the final result.
+// - **Commutativity**: `a.join(b) == b.join(a)`
+//   - The order of operands does not affect the join result.
+// - **Idempotence**: `a.join(a) == a`
+//   - Joining a value with itself yields the same value.
+// - **Identity Element**: `Bottom` acts as the identity element.
+//   - `a.join(Bottom) == a` and `Bottom.join(a) == a`
+// - **Absorbing Element**: `Top` absorbs every value.
+//   - `a.join(Top) == Top` and `Top.join(a) == Top`
+//
+// `Bottom` stands for no value at all (e.g. code that is never reached) and `Top`
+// for any value (e.g. when the analysis gave up after an error). Neither is the
+// runtime `undefined`, which is a value like any other.
 //
-// By leveraging these algebraic properties, we ensure that the merging process
-// is both predictable and (*ideally*) mathematically sound, which is crucial for accurate
-// abstract interpretation.
+// By leveraging these algebraic properties, we ensure that the joining process
+// is both predictable and mathematically sound, which is crucial for accurate
+// abstract interpretation. `join` is the least upper bound of the partial order
+// `leq` of the domain, and `types_tests` checks these laws on random values.
+
+// Loops are analyzed by fixpoint iteration: the loop head state is joined with
+// the state after one more run of the body until nothing changes. After
+// `WIDENING_DELAY` plain joins we switch to widening so that values which keep
+// growing (e.g. `x = [x]`) are collapsed and the iteration terminates.
+// `MAX_LOOP_ITERATIONS` is a hard cap in case widening is still not enough.
 //
-// The `Merge` trait defines the `merge` method, and it is implemented for
-// `AbstractValue`, allowing us to perform merges seamlessly across different
-// abstract value types.
+// Widening may overshoot, e.g. send the bound of a counter to infinity, so the
+// fixpoint is then refined by up to `NARROWING_ITERATIONS` more runs of the
+// body that may only make the loop head more precise.
+const WIDENING_DELAY: usize = 3;
+const MAX_LOOP_ITERATIONS: usize = 100;
+const NARROWING_ITERATIONS: usize = 3;
+
+// result of analyzing a whole program
+pub struct Analysis<V = AbstractValue> {
+    pub state: AbstractState<V>,
+    pub diagnostics: Vec<Diagnostic>,
+    // how often function summaries were reused
+    pub summary_stats: SummaryStats,
+}
+
+// Analyze `program` from an empty state, collecting every diagnostic reported
+// along the way (e.g. why a value became `Top`).
+pub fn analyze(program: &ASTNode) -> Analysis {
+    analyze_in(program, AbstractState::new())
+}
 
-pub trait Merge {
-    fn merge(&self, other: &Self) -> Self;
+// analyze `program`, distinguishing calls by their last `k` call sites
+pub fn analyze_with_call_string_length(program: &ASTNode, k: usize) -> Analysis {
+    analyze_in(program, AbstractState::with_call_string_length(k))
+}
+
+// analyze `program` from `state`, in any value domain (e.g. `Sign`)
+pub fn analyze_in<V: ValueDomain>(program: &ASTNode, mut state: AbstractState<V>) -> Analysis<V> {
+    interpret(program, &mut state);
+    // the loops whose body runs in none of the states they were reached in
+    let never_entered: Vec<Location> = state
+        .loops
+        .borrow()
+        .iter()
+        .filter(|(_, entered)| !entered)
+        .map(|(location, _)| *location)
+        .collect();
+    for location in never_entered {
+        let mut diagnostic = Diagnostic::warning(
+            DiagnosticKind::UnreachableCode,
+            "the loop body is never executed: its condition is always false",
+        );
+        diagnostic.location = Some(location);
+        state.report(diagnostic);
+    }
+    let diagnostics = std::mem::take(&mut state.diagnostics);
+    let summary_stats = state.summaries.borrow().stats();
+    Analysis {
+        state,
+        diagnostics,
+        summary_stats,
+    }
 }
 
 // Abstract interpretation
-pub fn interpret(node: &ASTNode, state: &mut AbstractState) -> AbstractValue {
+pub fn interpret<V: ValueDomain>(node: &ASTNode, state: &mut AbstractState<V>) -> V {
     match node {
-        ASTNode::Literal(value) => value.clone(),
-        ASTNode::Variable(name) => state.get(name).cloned().unwrap_or(AbstractValue::Undefined),
+        ASTNode::Literal(value) => V::literal(value),
+        ASTNode::StringLiteral(text) => V::string_literal(text),
+        ASTNode::Variable(name) => lookup_variable(name, state),
         ASTNode::Assignment { target, value } => {
             let abstract_value = interpret(value, state);
             state.assign(target, abstract_value.clone());
             abstract_value
         }
+        ASTNode::Declaration { target, value } => {
+            let abstract_value = interpret(value, state);
+            state.declare(target, abstract_value.clone());
+            abstract_value
+        }
         ASTNode::BinaryOp { op, left, right } => {
             let left_value = interpret(left, state);
+            // `&&` and `||` may not evaluate their right operand at all
+            if op == "&&" || op == "||" {
+                return abstract_logical(op, left_value, right, state);
+            }
             let right_value = interpret(right, state);
             // perform abstract operation based on operator
-            match op.as_str() {
-                "+" => abstract_add(&left_value, &right_value),
-                "-" => abstract_subtract(&left_value, &right_value),
-                "*" => abstract_multiply(&left_value, &right_value),
-                "/" => abstract_divide(&left_value, &right_value),
-                "==" => abstract_equal(&left_value, &right_value),
-                _ => AbstractValue::Undefined,
+            let Some(result) = V::binary(op, &left_value, &right_value) else {
+                state.report(Diagnostic::warning(
+                    DiagnosticKind::UnknownOperator,
+                    format!("unknown operator `{}`", op),
+                ));
+                return V::top();
+            };
+            // a `Top` operand has already been reported where it came from
+            if result == V::top() && left_value != V::top() && right_value != V::top() {
+                state.report(Diagnostic::warning(
+                    DiagnosticKind::InvalidOperand,
+                    format!(
+                        "operator `{}` is not defined for {:?} and {:?}",
+                        op, left_value, right_value
+                    ),
+                ));
             }
+            result
+        }
+        ASTNode::UnaryOp { op, operand } => {
+            let operand_value = interpret(operand, state);
+            V::unary(op, &operand_value).unwrap_or_else(|| {
+                state.report(Diagnostic::warning(
+                    DiagnosticKind::UnknownOperator,
+                    format!("unknown unary operator `{}`", op),
+                ));
+                V::top()
+            })
         }
         ASTNode::IfStatement {
             condition,
             then_branch,
             else_branch,
         } => {
-            let _condition_value = interpret(condition, state);
-            // consider both paths in the if statement
-            let mut then_state = state.clone();
-            let mut else_state = state.clone();
-            let then_value = interpret(then_branch, &mut then_state);
-            let else_value = if let Some(else_branch) = else_branch {
-                interpret(else_branch, &mut else_state)
+            let condition_value = interpret(condition, state);
+            // consider both paths in the if statement, each narrowed by the outcome
+            // of the condition. A path the condition rules out is skipped
+            let mut then_state = assume(condition, &condition_value, true, state);
+            let mut else_state = assume(condition, &condition_value, false, state);
+            let then_value = if then_state.unreachable {
+                V::bottom()
             } else {
-                AbstractValue::Undefined
+                interpret(then_branch, &mut then_state)
+            };
+            let else_value = match else_branch {
+                _ if else_state.unreachable => V::bottom(),
+                Some(else_branch) => interpret(else_branch, &mut else_state),
+                None => V::undefined(),
             };
             // merge states
-            state.merge(&then_state);
-            state.merge(&else_state);
-            then_value.merge(&else_value)
+            then_state.merge(&else_state);
+            *state = then_state;
+            then_value.join(&else_value)
         }
-        ASTNode::WhileLoop { condition: _, body } => {
-            // assume loop runs 0 or more times
-            let mut loop_state = state.clone();
-            interpret(body, &mut loop_state);
-            state.merge(&loop_state);
-            AbstractValue::Undefined
+        ASTNode::WhileLoop { condition, body } => {
+            // the loop runs 0 or more times, so the loop head state must cover
+            // the entry state and the state after any number of iterations.
+            // `head_value` covers the values of the condition at the loop head.
+            let mut head = state.clone();
+            let mut head_value = interpret(condition, &mut head);
+            let entry = head.clone();
+            // one more run of the body from `head`, which it enters only when the
+            // condition holds, and the value of the condition after it
+            let iterate = |head: &AbstractState<V>, head_value: &V| {
+                let mut body_state = assume(condition, head_value, true, head);
+                if !body_state.unreachable {
+                    interpret(body, &mut body_state);
+                }
+                let body_value = if body_state.unreachable {
+                    V::bottom()
+                } else {
+                    interpret(condition, &mut body_state)
+                };
+                (body_state, body_value)
+            };
+            let mut stable = false;
+            for iteration in 0..MAX_LOOP_ITERATIONS {
+                let (body_state, body_value) = iterate(&head, &head_value);
+                head_value = head_value.join(&body_value);
+
+                let mut next = head.clone();
+                if iteration < WIDENING_DELAY {
+                    next.merge(&body_state);
+                } else {
+                    next.widen(&body_state);
+                }
+
+                let changed = !next.same_variables(&head);
+                head = next;
+                if !changed {
+                    stable = true;
+                    break;
+                }
+            }
+            if !stable {
+                head.report(Diagnostic::warning(
+                    DiagnosticKind::LoopIterationLimit,
+                    format!(
+                        "loop did not stabilize after {} iterations; the result may be unsound",
+                        MAX_LOOP_ITERATIONS
+                    ),
+                ));
+            } else {
+                for _ in 0..NARROWING_ITERATIONS {
+                    // `head_value` still covers the narrowed head, which is smaller
+                    let mut next = entry.clone();
+                    next.merge(&iterate(&head, &head_value).0);
+                    let mut narrowed = head.clone();
+                    narrowed.narrow(&next);
+                    if narrowed.same_variables(&head) {
+                        break;
+                    }
+                    head = narrowed;
+                }
+            }
+            // a body never entered is reported at the end (see `analyze_in`)
+            state.record_loop(head_value.truthiness() != Some(false));
+            // the loop is left when the condition does not hold, never if it always does
+            *state = assume(condition, &head_value, false, &head);
+            V::undefined()
         }
         ASTNode::Block { statements } => {
-            let mut result = AbstractValue::Undefined;
-            for stmt in statements {
+            let mut result = V::undefined();
+            for (i, stmt) in statements.iter().enumerate() {
+                // statements after a definite `return` (or a branch or loop that is
+                // never left) are never executed. Only those after a statement that
+                // returns on every path are reported: the conditions that cut the
+                // other paths may depend on the arguments of the call being analyzed
+                if state.unreachable {
+                    if i > 0 && always_returns(&statements[i - 1]) {
+                        let mut diagnostic = Diagnostic::warning(
+                            DiagnosticKind::UnreachableCode,
+                            "unreachable code",
+                        );
+                        if let ASTNode::Located { location, .. } = stmt {
+                            diagnostic.location = Some(*location);
+                        }
+                        state.report(diagnostic);
+                    }
+                    break;
+                }
                 result = interpret(stmt, state);
             }
             result
@@ -97,6 +280,7 @@ pub fn interpret(node: &ASTNode, state: &mut AbstractState) -> AbstractValue {
             name,
             params,
             generics,
+            return_type,
             body,
         } => {
             // when we encounter a function declaration, we construct a `Function` struct.
@@ -117,166 +301,1127 @@ pub fn interpret(node: &ASTNode, state: &mut AbstractState) -> AbstractValue {
             let function = Function {
                 params: params.clone(),
                 generics: generics.clone(),
-                body: *body.clone(),
+                return_type: return_type.clone(),
+                body: body.clone(),
             };
-            // store the function in the state to allow it to be invoked later
+            let function = check_annotations(name, function, state);
+            // store the function in the current scope to allow it to be invoked later
             state.functions.insert(name.clone(), function);
             // return `Undefined` since defining a function
             // does not produce a value immediately.
-            AbstractValue::Undefined
+            V::undefined()
+        }
+        ASTNode::Return(value) => {
+            let return_value = match value {
+                Some(value) => interpret(value, state),
+                None => V::undefined(),
+            };
+            state.record_return(return_value.clone());
+            return_value
+        }
+        ASTNode::FunctionExpression {
+            name,
+            params,
+            generics,
+            return_type,
+            body,
+        } => {
+            // a function expression evaluates to a closure over the current scopes
+            let function = Function {
+                params: params.clone(),
+                generics: generics.clone(),
+                return_type: return_type.clone(),
+                body: body.clone(),
+            };
+            let function =
+                check_annotations(name.as_deref().unwrap_or("<anonymous>"), function, state);
+            let mut captured = state.capture(state.enclosing.len());
+            // a named function expression can refer to itself by its name
+            if let Some(name) = name {
+                let mut own_scope = Scope::default();
+                own_scope.functions.insert(name.clone(), function.clone());
+                captured.push(own_scope);
+            }
+            V::closure(Closure { function, captured })
         }
         ASTNode::FunctionCall {
             function,
             arguments,
         } => {
-            // When we encounter a function call, we assume that the `function` field contains
-            // the variable name of the function.
+            // When the `function` field is the name of a declared function, we call it
+            // directly. Otherwise the callee is evaluated like any other expression,
+            // and each function it may be is called.
             //
             // Example:
             // ```
             // result = add(5, 10);
+            // result = apply(add, 5);   // `f(x)` inside `apply` calls through `f`
             // ```
             //
             // Here, `add` is the variable name of the function.
+            if is_array_is_array(function) {
+                for arg_node in arguments {
+                    interpret(arg_node, state);
+                }
+                return V::boolean(None);
+            }
             if let ASTNode::Variable(func_name) = &**function {
-                // look up the function by its name in the current state
-                if let Some(func) = state.functions.get(func_name).cloned() {
-                    // create new abstract state for interpreting this function call.
-                    // this represents the local state/context within the function body.
-                    let mut func_state = AbstractState::new();
-
-                    // bind the provided arguments to the function's parameters.
-                    for (param, arg_node) in func.params.iter().zip(arguments.iter()) {
-                        let arg_value = interpret(arg_node, state);
-                        func_state.assign(param, arg_value);
-                    }
-
-                    // create a mapping of generic type parameters to concrete values provided during the call.
-                    let mut generic_mapping = HashMap::new();
-                    for (i, (generic, constraint)) in func.generics.iter().enumerate() {
-                        // for each generic parameter, retrieve the corresponding argument if available.
-                        if let Some(arg_node) = arguments.get(i) {
-                            let arg_value = interpret(arg_node, state);
-
-                            // check constraint
-                            if let Some(constraint_type) = constraint {
-                                if !satisfies_constraint(&arg_value, &constraint_type) {
-                                    // if the argument does not satisfy the constraint, return undefined
-                                    return AbstractValue::Undefined;
-                                }
-                            }
-
-                            // mapping the generic to the argument's type when satisfied constraint
-                            generic_mapping.insert(generic.clone(), Box::new(arg_value));
-                        }
-                    }
-
-                    // re-assign parameters with their arguments within the new function state for evaluation
-                    for (param, arg_node) in func.params.iter().zip(arguments.iter()) {
-                        let arg_value = interpret(arg_node, state);
-                        func_state.assign(param, arg_value);
+                // a variable holding a function value shadows declared functions
+                state.record_read(func_name);
+                if state.get(func_name).is_none() {
+                    // look up the function by its name in the scopes visible from here
+                    if let Some((depth, func)) = state.lookup_function(func_name) {
+                        let func = func.clone();
+                        let arg_values: Vec<V> =
+                            arguments.iter().map(|arg| interpret(arg, state)).collect();
+                        // the body runs in a new scope nested in the scope
+                        // the function was declared in (not the caller's)
+                        let func_state = state.enter_function(depth);
+                        return call_function(
+                            func_name,
+                            &func,
+                            &arg_values,
+                            func_state,
+                            depth + 1,
+                            state,
+                        );
                     }
+                    // not found in state
+                    state.report(Diagnostic::error(
+                        DiagnosticKind::UnknownFunction,
+                        format!("call to unknown function `{}`", func_name),
+                    ));
+                    return V::top();
+                }
+            }
 
-                    // Interpret the function body using the newly created function state.
-                    // During this step, any references to generics should be replaced with their concrete types.
-                    // This ensures that the function body operates with the correct types.
-                    let result =
-                        interpret_with_generics(&func.body, &mut func_state, &generic_mapping);
+            let callee = interpret(function, state);
+            let arg_values: Vec<V> = arguments.iter().map(|arg| interpret(arg, state)).collect();
+            let func_name = match &**function {
+                ASTNode::Variable(name) => name.as_str(),
+                _ => "<anonymous>",
+            };
 
-                    // return the result of interpreting the function body.
-                    //
-                    // TODO: if needed connect the result with concrete generics
-                    return result;
-                }
-                // not found in state
-                return AbstractValue::Undefined;
+            let Callees {
+                closures,
+                unknown,
+                not_callable,
+            } = callee.callees();
+            // an unknown callee has been reported where it came from
+            let mut result = unknown.then(V::top);
+            for closure in closures {
+                // only the global scope is shared with the caller
+                let func_state = state.enter_closure(&closure);
+                let value = call_function(
+                    func_name,
+                    &closure.function,
+                    &arg_values,
+                    func_state,
+                    1,
+                    state,
+                );
+                result = Some(match result {
+                    Some(result) => result.join(&value),
+                    None => value,
+                });
+            }
+            if !not_callable.is_empty() {
+                let message = format!("`{}` may be {:?}, not a function", func_name, not_callable);
+                state.report(if result.is_some() {
+                    Diagnostic::warning(DiagnosticKind::NotAFunction, message)
+                } else {
+                    Diagnostic::error(DiagnosticKind::NotAFunction, message)
+                });
+            }
+            match result {
+                Some(result) => result,
+                // no callee, no call
+                None if not_callable.is_empty() => V::bottom(),
+                None => V::top(),
             }
-            AbstractValue::Undefined
         }
         ASTNode::ArrayLiteral(elements) => {
             let avv = elements.iter().map(|elem| interpret(elem, state)).collect();
-            AbstractValue::Array(avv)
+            V::array(avv)
         }
         ASTNode::ArrayIndex { array, index } => {
             let array_value = interpret(array, state);
             let index_value = interpret(index, state);
+            V::read_element(&array_value, &index_value, state)
+        }
+        ASTNode::ObjectLiteral(props) => {
+            let props = props
+                .iter()
+                .map(|(key, value)| (key.clone(), interpret(value, state)))
+                .collect();
+            V::object(props)
+        }
+        ASTNode::PropertyAccess { object, property } => {
+            let object_value = interpret(object, state);
+            object_value.read_property(property, state)
+        }
+        ASTNode::PropertyAssignment {
+            object,
+            property,
+            value,
+        } => {
+            let value = interpret(value, state);
+            assign_property(object, property, value.clone(), state);
+            value
+        }
+        ASTNode::Located { location, node } => {
+            let outer = state.location.replace(*location);
+            let value = interpret(node, state);
+            state.location = outer;
+            value
+        }
+    }
+}
 
-            if !matches!(index_value, AbstractValue::Number) {
-                return AbstractValue::Undefined;
+// `array[index]` in the type-tag domain
+fn index_array(
+    array_value: &AbstractValue,
+    index_value: &AbstractValue,
+    state: &mut AbstractState,
+) -> AbstractValue {
+    match (array_value, index_value) {
+        (AbstractValue::Bottom, _) | (_, AbstractValue::Bottom) => return AbstractValue::Bottom,
+        (AbstractValue::Top, _) | (_, AbstractValue::Top) => return AbstractValue::Top,
+        _ => {}
+    }
+    let Some(index) = index_value.as_interval() else {
+        state.report(Diagnostic::warning(
+            DiagnosticKind::NonNumberIndex,
+            format!("array index {:?} is not a Number", index_value),
+        ));
+        return AbstractValue::Top;
+    };
+
+    match array_value {
+        AbstractValue::Array(array) => read_element(array, &index, state),
+        AbstractValue::Union(variants) => {
+            variants.iter().fold(AbstractValue::Bottom, |acc, variant| {
+                if let AbstractValue::Array(array) = variant {
+                    return acc.join(&read_element(array, &index, state));
+                }
+                acc.join(&AbstractValue::Undefined)
+            })
+        }
+        _ => {
+            state.report(Diagnostic::warning(
+                DiagnosticKind::NotAnArray,
+                format!("cannot index into {:?}", array_value),
+            ));
+            AbstractValue::Top
+        }
+    }
+}
+
+// the value of a variable, or a declared function used as a value
+fn lookup_variable<V: ValueDomain>(name: &str, state: &mut AbstractState<V>) -> V {
+    state.record_read(name);
+    if let Some(value) = state.get(name) {
+        return value.clone();
+    }
+    match state.lookup_function(name) {
+        Some((depth, function)) => V::closure(Closure {
+            function: function.clone(),
+            captured: state.capture(depth),
+        }),
+        None => {
+            state.report(Diagnostic::warning(
+                DiagnosticKind::UnknownVariable,
+                format!("variable `{}` is not defined", name),
+            ));
+            V::top()
+        }
+    }
+}
+
+// read `value.property`, warning when the property may not exist
+fn read_property(
+    value: &AbstractValue,
+    property: &str,
+    state: &mut AbstractState,
+) -> AbstractValue {
+    match value {
+        AbstractValue::Object(obj) => match obj.props.get(property) {
+            Some(prop) => prop.clone(),
+            None => {
+                state.report(Diagnostic::warning(
+                    DiagnosticKind::MissingProperty,
+                    format!("property `{}` does not exist on {:?}", property, value),
+                ));
+                AbstractValue::Undefined
             }
+        },
+        AbstractValue::Array(_) | AbstractValue::String if property == "length" => {
+            AbstractValue::Number
+        }
+        AbstractValue::Union(variants) => {
+            variants.iter().fold(AbstractValue::Bottom, |acc, variant| {
+                acc.join(&read_property(variant, property, state))
+            })
+        }
+        AbstractValue::Bottom | AbstractValue::Top => value.clone(),
+        _ => {
+            state.report(Diagnostic::warning(
+                DiagnosticKind::NotAnObject,
+                format!("cannot read property `{}` of {:?}", property, value),
+            ));
+            AbstractValue::Top
+        }
+    }
+}
 
-            let element_type = match array_value {
-                AbstractValue::Array(elements) => {
-                    // merge all elements
-                    elements
-                        .iter()
-                        .fold(AbstractValue::Undefined, |acc, elem| acc.merge(elem))
-                }
-                AbstractValue::Union(variants) => {
-                    variants
-                        .iter()
-                        .fold(AbstractValue::Undefined, |acc, variant| {
-                            if let AbstractValue::Array(elements) = variant {
-                                let elem_type = elements
-                                    .iter()
-                                    .fold(AbstractValue::Undefined, |e_acc, e| e_acc.merge(e));
-                                return acc.merge(&elem_type);
-                            }
-                            acc.merge(&AbstractValue::Undefined)
-                        })
-                }
-                _ => AbstractValue::Undefined,
-            };
-            element_type
+// `object.property = value`, writing the updated object back to where it came from
+fn assign_property<V: ValueDomain>(
+    object: &ASTNode,
+    property: &str,
+    value: V,
+    state: &mut AbstractState<V>,
+) {
+    update_target(
+        object,
+        &mut |current: V, state: &mut AbstractState<V>| {
+            current.set_property(property, value.clone(), state)
+        },
+        state,
+    );
+}
+
+// Replace the value of `target` by `update` of its current value, unless it
+// gives `None`. The objects along a chain like `o.a` are each evaluated once:
+// `o.a.b = v` reads `o` and then `o.a`, and writes `o.a = { ...o.a, b: v }`.
+fn update_target<V: ValueDomain>(
+    target: &ASTNode,
+    update: &mut dyn FnMut(V, &mut AbstractState<V>) -> Option<V>,
+    state: &mut AbstractState<V>,
+) {
+    match target {
+        ASTNode::Variable(name) => {
+            let current = lookup_variable(name, state);
+            if let Some(updated) = update(current, state) {
+                state.assign(name, updated);
+            }
+        }
+        ASTNode::PropertyAccess { object, property } => update_target(
+            object,
+            &mut |parent: V, state: &mut AbstractState<V>| {
+                // a property missing from `parent` is reported by `update`,
+                // which cannot write to it
+                let reported = state.diagnostics.len();
+                let current = parent.read_property(property, state);
+                let read = state.diagnostics.split_off(reported);
+                state.diagnostics.extend(
+                    read.into_iter()
+                        .filter(|d| d.kind != DiagnosticKind::MissingProperty),
+                );
+                let updated = update(current, state)?;
+                parent.set_property(property, updated, state)
+            },
+            state,
+        ),
+        // the object is a temporary, so the update is not observable
+        _ => {
+            let current = interpret(target, state);
+            update(current, state);
+        }
+    }
+}
+
+// `object` with `property` set to `value`, `None` when no object it may be
+// can have the property set (e.g. `undefined`)
+fn set_property(
+    object: &AbstractValue,
+    property: &str,
+    value: AbstractValue,
+    state: &mut AbstractState,
+) -> Option<AbstractValue> {
+    match object {
+        AbstractValue::Object(obj) => {
+            let mut obj = obj.clone();
+            obj.props.insert(property.to_string(), value);
+            Some(AbstractValue::Object(obj))
+        }
+        // every object the value may be gets the property, the other variants
+        // are left as they are
+        AbstractValue::Union(variants) => {
+            let updated: Vec<_> = variants
+                .iter()
+                .map(|variant| set_property(variant, property, value.clone(), state))
+                .collect();
+            if updated.iter().all(Option::is_none) {
+                return None;
+            }
+            Some(AbstractValue::union_of(
+                updated
+                    .into_iter()
+                    .zip(variants.iter())
+                    .map(|(updated, variant)| updated.unwrap_or_else(|| variant.clone())),
+            ))
+        }
+        AbstractValue::Bottom | AbstractValue::Top => None,
+        _ => {
+            state.report(Diagnostic::warning(
+                DiagnosticKind::NotAnObject,
+                format!("cannot set property `{}` on {:?}", property, object),
+            ));
+            None
         }
     }
 }
 
+// Interpret a call of `func` with the already evaluated arguments.
+//
+// `func_state` is the state the body starts in (see `AbstractState::enter_function`
+// and `AbstractState::enter_closure`); the first `live` scopes around the body are
+// shared with the caller and written back to `state` afterwards.
+fn call_function<V: ValueDomain>(
+    func_name: &str,
+    func: &Function,
+    arg_values: &[V],
+    func_state: AbstractState<V>,
+    live: usize,
+    state: &mut AbstractState<V>,
+) -> V {
+    let generic_mapping = match V::bind_generics(func_name, func, arg_values) {
+        Ok(generic_mapping) => generic_mapping,
+        Err(error) => {
+            state.report(error);
+            return V::top();
+        }
+    };
+
+    let summaries = state.summaries.clone();
+    let key = summaries.borrow().key(func, state.location, arg_values);
+    // a recursive call: use what is known about the result so far
+    if let Some(result) = summaries.borrow_mut().recursive_result(&key) {
+        return result;
+    }
+    if summaries.borrow().depth() >= MAX_CALL_DEPTH {
+        state.report(Diagnostic::warning(
+            DiagnosticKind::RecursionLimit,
+            format!(
+                "call to `{}` is nested more than {} calls deep and was not analyzed",
+                func_name, MAX_CALL_DEPTH
+            ),
+        ));
+        return V::top();
+    }
+
+    // the same call has been analyzed before
+    if let Some(summary) = summaries.borrow_mut().cached(&key, &func_state.enclosing) {
+        return apply_summary(summary, live, state);
+    }
+
+    summaries
+        .borrow_mut()
+        .enter(&key, state.location, &func_state.enclosing);
+    let mut stable = false;
+    let mut result = V::bottom();
+    let mut body_state = func_state.clone();
+    for _ in 0..MAX_RECURSION_ITERATIONS {
+        body_state = func_state.clone();
+        // bind the provided arguments to the function's parameters,
+        // missing arguments are `undefined`
+        for (i, (param, _)) in func.params.iter().enumerate() {
+            let arg_value = key.arguments.get(i).cloned().unwrap_or_else(V::undefined);
+            body_state.declare(param, arg_value);
+        }
+
+        // Interpret the function body using the newly created function state.
+        // During this step, any references to generics should be replaced with their concrete types.
+        // This ensures that the function body operates with the correct types.
+        let body_value = interpret_with_generics(&func.body, &mut body_state, &generic_mapping);
+        result = function_result(&func.body, body_value, &body_state);
+
+        // iterate until the result assumed for recursive calls covers the actual one
+        if summaries.borrow_mut().update(&result) {
+            stable = true;
+            break;
+        }
+    }
+
+    // only the diagnostics and effects of the last iteration are kept
+    let mut diagnostics = body_state.diagnostics;
+    if !stable {
+        diagnostics.push(Diagnostic::warning(
+            DiagnosticKind::RecursionLimit,
+            format!(
+                "result of recursive `{}` did not stabilize after {} iterations",
+                func_name, MAX_RECURSION_ITERATIONS
+            ),
+        ));
+    }
+    let result = V::check_return(func_name, func, result, &generic_mapping, &mut diagnostics);
+    let summary = CallSummary::new(
+        result,
+        diagnostics,
+        &func_state.enclosing,
+        &body_state.enclosing,
+    );
+    summaries.borrow_mut().exit(summary.clone());
+
+    apply_summary(summary, live, state)
+}
+
+// report what a call did in the caller's `state`, returning its result
+fn apply_summary<V: ValueDomain>(
+    summary: CallSummary<V>,
+    live: usize,
+    state: &mut AbstractState<V>,
+) -> V {
+    for diagnostic in summary.diagnostics {
+        state.report(diagnostic);
+    }
+    // the body may have assigned to variables of the enclosing scopes
+    state.leave_function(&summary.effects, live);
+    summary.result
+}
+
+// Bind the generics of `func` from the arguments of the annotated parameters,
+// and check their constraints, which may refer to the other generics
+// (`U extends T`), against what was inferred before any conflict.
+fn check_generics(
+    func_name: &str,
+    func: &Function,
+    arg_values: &[AbstractValue],
+) -> Result<Generics<AbstractValue>, Diagnostic> {
+    let (generic_mapping, inference_error) = infer_generics(func_name, func, arg_values);
+    for (generic, constraint) in &func.generics {
+        if let (Some(arg_value), Some(constraint_type)) = (generic_mapping.get(generic), constraint)
+        {
+            if !satisfies_constraint(arg_value, constraint_type, &generic_mapping) {
+                // if the argument does not satisfy the constraint, give up on the call
+                return Err(Diagnostic::error(
+                    DiagnosticKind::ConstraintViolation,
+                    format!(
+                        "argument {:?} of `{}` does not satisfy `{}: {}`",
+                        arg_value, func_name, generic, constraint_type
+                    ),
+                ));
+            }
+        }
+    }
+    match inference_error {
+        Some(error) => Err(error),
+        None => Ok(generic_mapping),
+    }
+}
+
+// `func` as declared, without the annotations and constraints naming a type
+// that is neither known nor one of its generics (e.g. a misspelled one). No
+// value has such a type, so rather than failing every call, each is reported
+// here, once, and then ignored.
+fn check_annotations<V: ValueDomain>(
+    func_name: &str,
+    mut func: Function,
+    state: &mut AbstractState<V>,
+) -> Function {
+    let generics: Vec<String> = func.generics.iter().map(|(name, _)| name.clone()).collect();
+    let mut report = |annotation: String, ty: &TypeExpr| {
+        let Some(name) = ty.unknown_name(&generics) else {
+            return true;
+        };
+        state.report(Diagnostic::error(
+            DiagnosticKind::UnknownType,
+            format!(
+                "{} of `{}` names `{}`, which is neither a type nor a generic",
+                annotation, func_name, name
+            ),
+        ));
+        false
+    };
+    for (name, annotation) in func.params.iter_mut().chain(&mut func.generics) {
+        if let Some(ty) = annotation {
+            if !report(format!("`{}: {}`", name, ty), ty) {
+                *annotation = None;
+            }
+        }
+    }
+    if let Some(ty) = &func.return_type {
+        if !report(format!("the return type `{}`", ty), ty) {
+            func.return_type = None;
+        }
+    }
+
+    // a generic only the return type names is bound by no call, and is taken
+    // to be anything when the returned value is checked
+    if let Some(ty) = &func.return_type {
+        let unbound = generics.iter().find(|generic| {
+            let generic = std::slice::from_ref(*generic);
+            ty.mentions(generic)
+                && !func
+                    .params
+                    .iter()
+                    .filter_map(|(_, annotation)| annotation.as_ref())
+                    .any(|annotation| annotation.mentions(generic))
+        });
+        if let Some(generic) = unbound {
+            state.report(Diagnostic::error(
+                DiagnosticKind::UnboundGeneric,
+                format!(
+                    "the return type `{}` of `{}` names `{}`, which no parameter annotation binds",
+                    ty, func_name, generic
+                ),
+            ));
+        }
+    }
+    func
+}
+
+// The value of a call whose body returned `result`, checked against the
+// declared return type. The declared type is the value of the call when
+// `result` does not have it, or nothing is known about `result`.
+fn check_return_type(
+    func_name: &str,
+    func: &Function,
+    result: AbstractValue,
+    generic_mapping: &Generics<AbstractValue>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> AbstractValue {
+    let Some(ty) = &func.return_type else {
+        return result;
+    };
+    // a generic the arguments do not bind (e.g. they are `Top`) may be anything
+    let mut generic_mapping = generic_mapping.clone();
+    for (generic, _) in &func.generics {
+        generic_mapping
+            .entry(generic.clone())
+            .or_insert_with(|| Box::new(AbstractValue::Top));
+    }
+    // every name is a generic, those that are not having been dropped at the declaration
+    let Some(declared) = ty.instantiate(&generic_mapping) else {
+        return result;
+    };
+    // the body's value is more precise than the declared one when it has the
+    // type, e.g. it knows the length of an array
+    if ty.admits(&result, &generic_mapping) {
+        return match result {
+            AbstractValue::Top => declared,
+            _ => result,
+        };
+    }
+    diagnostics.push(Diagnostic::error(
+        DiagnosticKind::TypeMismatch,
+        format!(
+            "`{}` returns {:?}, which does not match its return type `{}`",
+            func_name, result, ty
+        ),
+    ));
+    declared
+}
+
+// Bind the generics of `func` by unifying the annotation of each parameter
+// with its argument. Inference stops at the first argument that does not fit
+// its annotation or binds a generic to a second type, which is returned as
+// the error along with the generics inferred until then.
+//
+// A generic no annotation mentions is left unbound.
+fn infer_generics(
+    func_name: &str,
+    func: &Function,
+    arg_values: &[AbstractValue],
+) -> (Generics<AbstractValue>, Option<Diagnostic>) {
+    let generics: Vec<String> = func.generics.iter().map(|(name, _)| name.clone()).collect();
+    let mut mapping = HashMap::new();
+    let mut error = None;
+    for (i, (param, annotation)) in func.params.iter().enumerate() {
+        let Some(ty) = annotation else {
+            continue;
+        };
+        let arg_value = arg_values.get(i).unwrap_or(&AbstractValue::Undefined);
+        error = match ty.unify(arg_value, &generics, &mut mapping) {
+            Ok(()) => continue,
+            Err(UnifyError::Mismatch) => Some(Diagnostic::error(
+                DiagnosticKind::TypeMismatch,
+                format!(
+                    "argument {:?} of `{}` does not match `{}: {}`",
+                    arg_value, func_name, param, ty
+                ),
+            )),
+            Err(UnifyError::Conflict {
+                generic,
+                bound,
+                found,
+            }) => Some(Diagnostic::error(
+                DiagnosticKind::GenericConflict,
+                format!(
+                    "`{}` of `{}` is inferred as both {:?} and {:?} (from `{}`)",
+                    generic, func_name, bound, found, param
+                ),
+            )),
+        };
+        break;
+    }
+
+    (mapping, error)
+}
+
+// Whether every path through `stmt` ends in a `return`, whatever its
+// conditions evaluate to, e.g. an `if` returning from both branches.
+fn always_returns(stmt: &ASTNode) -> bool {
+    match stmt {
+        ASTNode::Return(_) => true,
+        ASTNode::Block { statements } => statements.iter().any(always_returns),
+        ASTNode::IfStatement {
+            then_branch,
+            else_branch: Some(else_branch),
+            ..
+        } => always_returns(then_branch) && always_returns(else_branch),
+        ASTNode::Located { node, .. } => always_returns(node),
+        _ => false,
+    }
+}
+
+// The value a call evaluates to, given the state after interpreting the body.
+//
+// A block body returns the merge of every reachable `return`, plus `undefined`
+// when some path falls off the end. Any other body is an expression body (like
+// an arrow function `(x) => x`) and evaluates to its own value.
+fn function_result<V: ValueDomain>(
+    body: &ASTNode,
+    body_value: V,
+    func_state: &AbstractState<V>,
+) -> V {
+    if !matches!(body, ASTNode::Block { .. }) {
+        return body_value;
+    }
+    match &func_state.return_value {
+        Some(value) if func_state.unreachable => value.clone(),
+        Some(value) => value.join(&V::undefined()),
+        // no path returns or falls off the end
+        None if func_state.unreachable => V::bottom(),
+        None => V::undefined(),
+    }
+}
+
+// The type-tag domain, in which `analyze` evaluates programs: the functions
+// below are its operators.
+impl ValueDomain for AbstractValue {
+    // a number is the interval holding just its value
+    fn literal(literal: &Literal) -> Self {
+        match *literal {
+            Literal::Number(n) => AbstractValue::number(Interval::constant(n)),
+            Literal::Boolean(b) => AbstractValue::BooleanLiteral(b),
+            Literal::Null => AbstractValue::Null,
+            Literal::Undefined => AbstractValue::Undefined,
+        }
+    }
+
+    fn string_literal(text: &str) -> Self {
+        AbstractValue::StringLiteral(text.to_string())
+    }
+
+    fn undefined() -> Self {
+        AbstractValue::Undefined
+    }
+
+    fn boolean(outcome: Option<bool>) -> Self {
+        boolean(outcome)
+    }
+
+    fn binary(op: &str, left: &Self, right: &Self) -> Option<Self> {
+        Some(match op {
+            "+" => abstract_add(left, right),
+            "-" => abstract_subtract(left, right),
+            "*" => abstract_multiply(left, right),
+            "/" => abstract_divide(left, right),
+            "%" => abstract_modulo(left, right),
+            "**" => abstract_power(left, right),
+            "==" | "!=" | "===" | "!==" => abstract_equal(op, left, right),
+            "<" | "<=" | ">" | ">=" => abstract_compare(op, left, right),
+            _ => return None,
+        })
+    }
+
+    fn unary(op: &str, operand: &Self) -> Option<Self> {
+        Some(match op {
+            "!" => abstract_not(operand),
+            "-" => abstract_negate(operand),
+            "typeof" => abstract_typeof(operand),
+            _ => return None,
+        })
+    }
+
+    fn truthiness(&self) -> Option<bool> {
+        AbstractValue::truthiness(self)
+    }
+
+    fn truthy_part(&self) -> Option<Self> {
+        AbstractValue::truthy_part(self)
+    }
+
+    fn falsy_part(&self) -> Option<Self> {
+        AbstractValue::falsy_part(self)
+    }
+
+    fn refine(condition: &ASTNode, assume: bool, state: &mut AbstractState) {
+        refine(condition, assume, state)
+    }
+
+    fn base_type(&self) -> Self {
+        AbstractValue::base_type(self)
+    }
+
+    fn array(elements: Vec<Self>) -> Self {
+        AbstractValue::tuple(elements)
+    }
+
+    fn read_element(array: &Self, index: &Self, state: &mut AbstractState) -> Self {
+        index_array(array, index, state)
+    }
+
+    fn object(props: BTreeMap<String, Self>) -> Self {
+        AbstractValue::Object(AbstractObject { props })
+    }
+
+    fn read_property(&self, property: &str, state: &mut AbstractState) -> Self {
+        read_property(self, property, state)
+    }
+
+    fn set_property(&self, property: &str, value: Self, state: &mut AbstractState) -> Option<Self> {
+        set_property(self, property, value, state)
+    }
+
+    fn closure(closure: Closure) -> Self {
+        AbstractValue::Function(closure)
+    }
+
+    fn callees(&self) -> Callees<Self> {
+        let variants = match self {
+            AbstractValue::Union(variants) => variants.to_vec(),
+            _ => vec![self.clone()],
+        };
+        let mut callees = Callees {
+            closures: Vec::new(),
+            unknown: false,
+            not_callable: Vec::new(),
+        };
+        for variant in variants {
+            match variant {
+                AbstractValue::Bottom => {}
+                AbstractValue::Top => callees.unknown = true,
+                AbstractValue::Function(closure) => callees.closures.push(closure),
+                other => callees.not_callable.push(other),
+            }
+        }
+        callees
+    }
+
+    fn bind_generics(
+        name: &str,
+        func: &Function,
+        arguments: &[Self],
+    ) -> Result<Generics<Self>, Diagnostic> {
+        check_generics(name, func, arguments)
+    }
+
+    fn check_return(
+        name: &str,
+        func: &Function,
+        result: Self,
+        generics: &Generics<Self>,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Self {
+        check_return_type(name, func, result, generics, diagnostics)
+    }
+}
+
+// The arithmetic operators are defined for numbers (`+` also for strings). An
+// operand with no value (`Bottom`) gives no value, and an undefined combination
+// gives `Top`, which is reported. Literals are folded: `1 + 2` is `3` and
+// `"a" + 1` is `"a1"`.
+
 fn abstract_add(left: &AbstractValue, right: &AbstractValue) -> AbstractValue {
+    let is_string =
+        |v: &AbstractValue| matches!(v, AbstractValue::String | AbstractValue::StringLiteral(_));
     match (left, right) {
-        (AbstractValue::Number, AbstractValue::Number) => AbstractValue::Number,
-        (AbstractValue::String, _) | (_, AbstractValue::String) => AbstractValue::String,
-        _ => AbstractValue::Union(vec![AbstractValue::Number, AbstractValue::String]),
+        (AbstractValue::Bottom, _) | (_, AbstractValue::Bottom) => AbstractValue::Bottom,
+        _ if is_string(left) || is_string(right) => match (text(left), text(right)) {
+            (Some(a), Some(b)) => AbstractValue::StringLiteral(a + &b),
+            _ => AbstractValue::String,
+        },
+        _ => match arithmetic(left, right, Interval::add) {
+            AbstractValue::Top => {
+                AbstractValue::union_of([AbstractValue::Number, AbstractValue::String])
+            }
+            sum => sum,
+        },
     }
 }
 
 fn abstract_subtract(left: &AbstractValue, right: &AbstractValue) -> AbstractValue {
-    if matches!(left, AbstractValue::Number) && matches!(right, AbstractValue::Number) {
-        AbstractValue::Number
-    } else {
-        AbstractValue::Undefined
-    }
+    arithmetic(left, right, Interval::subtract)
 }
 
 fn abstract_multiply(left: &AbstractValue, right: &AbstractValue) -> AbstractValue {
-    if matches!(left, AbstractValue::Number) && matches!(right, AbstractValue::Number) {
-        AbstractValue::Number
+    arithmetic(left, right, Interval::multiply)
+}
+
+fn abstract_divide(left: &AbstractValue, right: &AbstractValue) -> AbstractValue {
+    arithmetic(left, right, Interval::divide)
+}
+
+// the bounds of `%` and `**` are not tracked
+fn abstract_modulo(left: &AbstractValue, right: &AbstractValue) -> AbstractValue {
+    arithmetic(left, right, |_, _| None)
+}
+
+fn abstract_power(left: &AbstractValue, right: &AbstractValue) -> AbstractValue {
+    arithmetic(left, right, |_, _| None)
+}
+
+// `op` on the bounds of two numbers, where `None` is any number
+fn arithmetic(
+    left: &AbstractValue,
+    right: &AbstractValue,
+    op: fn(&Interval, &Interval) -> Option<Interval>,
+) -> AbstractValue {
+    match (left, right) {
+        (AbstractValue::Bottom, _) | (_, AbstractValue::Bottom) => AbstractValue::Bottom,
+        // which may be `NaN` or an infinity, unlike the numbers of an interval
+        (AbstractValue::Number, AbstractValue::Number | AbstractValue::Interval(_))
+        | (AbstractValue::Interval(_), AbstractValue::Number) => AbstractValue::Number,
+        _ => match (left.as_interval(), right.as_interval()) {
+            (Some(a), Some(b)) => AbstractValue::number(op(&a, &b)),
+            _ => AbstractValue::Top,
+        },
+    }
+}
+
+// the text of a literal when converted to a string, e.g. by `+`
+fn text(value: &AbstractValue) -> Option<String> {
+    match value {
+        AbstractValue::StringLiteral(text) => Some(text.clone()),
+        AbstractValue::BooleanLiteral(b) => Some(b.to_string()),
+        AbstractValue::Null => Some("null".to_string()),
+        AbstractValue::Undefined => Some("undefined".to_string()),
+        AbstractValue::Interval(i) if i.lo == i.hi => {
+            // where Rust and JavaScript write numbers the same way
+            let n = i.lo + 0.0;
+            (n == 0.0 || (1e-6..1e21).contains(&n.abs())).then(|| n.to_string())
+        }
+        _ => None,
+    }
+}
+
+// The remaining operators are defined for any operand, so only an operand with
+// no value makes a difference. Their result is a literal when it does not
+// depend on the runtime values: `1 === 2` is `false`.
+
+// `true` or `false` when `outcome` is known, any boolean otherwise
+fn boolean(outcome: Option<bool>) -> AbstractValue {
+    match outcome {
+        Some(b) => AbstractValue::BooleanLiteral(b),
+        None => AbstractValue::Boolean,
+    }
+}
+
+fn abstract_equal(op: &str, left: &AbstractValue, right: &AbstractValue) -> AbstractValue {
+    let strict = op.len() == 3;
+    let negated = op.starts_with('!');
+    let variants = |v: &AbstractValue| match v {
+        AbstractValue::Union(variants) => variants.to_vec(),
+        _ => vec![v.clone()],
+    };
+    // the outcome must be the same for every pair of variants
+    let mut outcomes = variants(left).into_iter().flat_map(|a| {
+        variants(right)
+            .into_iter()
+            .map(move |b| equals(&a, &b, strict))
+    });
+    let first = outcomes.next().flatten();
+    let outcome = if outcomes.all(|o| o == first) {
+        first
     } else {
-        AbstractValue::Undefined
+        None
+    };
+    or_bottom(
+        &[left, right],
+        boolean(outcome.map(|equal| equal != negated)),
+    )
+}
+
+// Whether `a == b` (`a === b` when `strict`) for every runtime value of `a`
+// and `b`, which are not unions (`Some(true)`), for none (`Some(false)`), or
+// depends on them (`None`).
+fn equals(a: &AbstractValue, b: &AbstractValue, strict: bool) -> Option<bool> {
+    use AbstractValue::*;
+
+    match (a, b) {
+        (Generic(_, a), _) => equals(a, b, strict),
+        (_, Generic(_, b)) => equals(a, b, strict),
+        (Top, _) | (_, Top) => None,
+        (Null | Undefined, Null | Undefined) => Some(!strict || a == b),
+        // `null` and `undefined` are only loosely equal to each other
+        (Null | Undefined, _) | (_, Null | Undefined) => Some(false),
+        (StringLiteral(x), StringLiteral(y)) => Some(x == y),
+        (BooleanLiteral(x), BooleanLiteral(y)) => Some(x == y),
+        _ => match (a.as_interval(), b.as_interval()) {
+            (Some(x), Some(y)) => x.compare("==", &y),
+            // loose equality converts values of other kinds
+            _ if strict && a.rank() != b.rank() => Some(false),
+            _ => None,
+        },
     }
 }
 
-fn abstract_divide(left: &AbstractValue, right: &AbstractValue) -> AbstractValue {
-    if matches!(left, AbstractValue::Number) && matches!(right, AbstractValue::Number) {
-        AbstractValue::Number
+// `<`, `<=`, `>`, `>=` compare any two values (possibly after conversion),
+// and the bounds of numbers
+fn abstract_compare(op: &str, left: &AbstractValue, right: &AbstractValue) -> AbstractValue {
+    let outcome = match (left.as_interval(), right.as_interval()) {
+        (Some(a), Some(b)) => a.compare(op, &b),
+        _ => None,
+    };
+    or_bottom(&[left, right], boolean(outcome))
+}
+
+fn abstract_not(operand: &AbstractValue) -> AbstractValue {
+    or_bottom(&[operand], boolean(operand.truthiness().map(|b| !b)))
+}
+
+// unary minus converts its operand to a number (possibly `NaN`, still a number)
+fn abstract_negate(operand: &AbstractValue) -> AbstractValue {
+    match operand.as_interval() {
+        Some(interval) => AbstractValue::number(Some(interval.negate())),
+        None => or_bottom(&[operand], AbstractValue::Number),
+    }
+}
+
+// the name of the type, when every variant has the same one
+fn abstract_typeof(operand: &AbstractValue) -> AbstractValue {
+    fn type_name(value: &AbstractValue) -> Option<&'static str> {
+        Some(match value {
+            AbstractValue::Undefined => "undefined",
+            AbstractValue::Null | AbstractValue::Object(_) | AbstractValue::Array(_) => "object",
+            AbstractValue::Boolean | AbstractValue::BooleanLiteral(_) => "boolean",
+            AbstractValue::Number | AbstractValue::Interval(_) => "number",
+            AbstractValue::String | AbstractValue::StringLiteral(_) => "string",
+            AbstractValue::Function(_) => "function",
+            AbstractValue::Generic(_, concrete) => type_name(concrete)?,
+            AbstractValue::Union(variants) => {
+                let first = type_name(variants.first()?)?;
+                variants
+                    .iter()
+                    .all(|v| type_name(v) == Some(first))
+                    .then_some(first)?
+            }
+            AbstractValue::Bottom | AbstractValue::Top => return None,
+        })
+    }
+    let result = match type_name(operand) {
+        Some(name) => AbstractValue::StringLiteral(name.to_string()),
+        None => AbstractValue::String,
+    };
+    or_bottom(&[operand], result)
+}
+
+// `Bottom` if any of `operands` is, `result` otherwise
+fn or_bottom(operands: &[&AbstractValue], result: AbstractValue) -> AbstractValue {
+    if operands.contains(&&AbstractValue::Bottom) {
+        AbstractValue::Bottom
     } else {
-        AbstractValue::Undefined
+        result
+    }
+}
+
+// Read the elements of `array` at an index within `index`.
+//
+// The result merges the slots the index may select, and `undefined` when it
+// may select none (negative, fractional or past the length the array has at
+// least), which is reported.
+fn read_element(
+    array: &AbstractArray,
+    index: &Interval,
+    state: &mut AbstractState,
+) -> AbstractValue {
+    let elements = array.elements();
+    let first_slot = index.lo.max(0.0).ceil();
+    let last_slot = index.hi.floor();
+    let mut element = elements
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| first_slot <= *i as f64 && *i as f64 <= last_slot)
+        .fold(AbstractValue::Bottom, |acc, (_, elem)| acc.join(elem));
+    if first_slot <= last_slot && last_slot >= elements.len() as f64 {
+        element = element.join(array.rest());
+    }
+
+    // only the slots before the length the array has at least surely exist
+    let inside = index.lo >= 0.0 && index.hi < array.min_length() as f64 && index.integral;
+    if inside {
+        return element;
     }
+    let length = match (array.rest(), array.min_length()) {
+        (AbstractValue::Bottom, min) if min == elements.len() => min.to_string(),
+        (AbstractValue::Bottom, min) => format!("{} to {}", min, elements.len()),
+        (_, min) => format!("at least {}", min),
+    };
+    let message = if element == AbstractValue::Bottom {
+        format!(
+            "index {:?} is out of bounds for an array of length {}",
+            index, length
+        )
+    } else {
+        format!(
+            "index {:?} may be out of bounds for an array of length {}",
+            index, length
+        )
+    };
+    state.report(Diagnostic::warning(
+        DiagnosticKind::IndexOutOfBounds,
+        message,
+    ));
+    element.or_undefined()
 }
 
-fn abstract_equal(_left: &AbstractValue, _right: &AbstractValue) -> AbstractValue {
-    AbstractValue::Boolean
-}
+// `a && b` is `a` when `a` is falsy and `b` otherwise; `a || b` is `a` when `a`
+// is truthy and `b` otherwise. The right operand is only interpreted (and its
+// effects on the state only kept) when the left one may not short-circuit.
+fn abstract_logical<V: ValueDomain>(
+    op: &str,
+    left_value: V,
+    right: &ASTNode,
+    state: &mut AbstractState<V>,
+) -> V {
+    // the part of the left operand that is returned as is, and whether
+    // the right operand may be evaluated
+    let (short_circuit, evaluates_right) = if op == "&&" {
+        (
+            left_value.falsy_part(),
+            left_value.truthiness() != Some(false),
+        )
+    } else {
+        (
+            left_value.truthy_part(),
+            left_value.truthiness() != Some(true),
+        )
+    };
+    if !evaluates_right {
+        return left_value;
+    }
 
-pub fn merge_values(a: &AbstractValue, b: &AbstractValue) -> AbstractValue {
-    a.merge(b)
+    let mut right_state = state.clone();
+    let right_value = interpret(right, &mut right_state);
+    match short_circuit {
+        Some(kept) => {
+            state.merge(&right_state);
+            kept.join(&right_value)
+        }
+        None => {
+            *state = right_state;
+            right_value
+        }
+    }
 }
 
-fn interpret_with_generics(
+fn interpret_with_generics<V: ValueDomain>(
     node: &ASTNode,
-    state: &mut AbstractState,
-    mapping: &HashMap<String, Box<AbstractValue>>,
-) -> AbstractValue {
+    state: &mut AbstractState<V>,
+    mapping: &Generics<V>,
+) -> V {
     match node {
         ASTNode::IfStatement {
             condition,
@@ -284,24 +1429,30 @@ fn interpret_with_generics(
             else_branch,
         } => {
             // If statement handling to narrow the type based on conditions.
-            // For example, if (x == 42), we can narrow x to Number.
-            let mut then_state = state.clone();
-            let mut else_state = state.clone();
-
-            // Interpret the then-branch in a potentially narrowed context
-            let then_value = interpret_with_generics(then_branch, &mut then_state, mapping);
+            // For example, if (typeof x === "number"), we can narrow x to Number.
+            let condition_value = interpret_with_generics(condition, state, mapping);
+            let mut then_state = assume(condition, &condition_value, true, state);
+            let mut else_state = assume(condition, &condition_value, false, state);
+
+            // Interpret the then-branch in a potentially narrowed context,
+            // unless the condition rules it out
+            let then_value = if then_state.unreachable {
+                V::bottom()
+            } else {
+                interpret_with_generics(then_branch, &mut then_state, mapping)
+            };
 
             // Interpret the else-branch if present
-            let else_value = if let Some(else_branch) = else_branch {
-                interpret_with_generics(else_branch, &mut else_state, mapping)
-            } else {
-                AbstractValue::Undefined
+            let else_value = match else_branch {
+                _ if else_state.unreachable => V::bottom(),
+                Some(else_branch) => interpret_with_generics(else_branch, &mut else_state, mapping),
+                None => V::undefined(),
             };
 
             // Merge states and return the appropriate value
-            state.merge(&then_state);
-            state.merge(&else_state);
-            then_value.merge(&else_value)
+            then_state.merge(&else_state);
+            *state = then_state;
+            then_value.join(&else_value)
         }
 
         ASTNode::Variable(name) => {
@@ -309,30 +1460,49 @@ fn interpret_with_generics(
             if let Some(value) = mapping.get(name) {
                 return *value.clone();
             }
-            state.get(name).cloned().unwrap_or(AbstractValue::Undefined)
+            lookup_variable(name, state)
         }
 
         _ => interpret(node, state), // default handling
     }
 }
 
-// check if the value satisfies the constraint
-fn satisfies_constraint(v: &AbstractValue, constraint: &str) -> bool {
-    match constraint {
-        "Number" => matches!(v, AbstractValue::Number),
-        "String" => matches!(v, AbstractValue::String),
-        "Boolean" => matches!(v, AbstractValue::Boolean),
-        // TODO: add more constraints
-        _ => false,
-    }
+// check if the value satisfies the constraint, a type expression over the
+// generics bound in `mapping`
+fn satisfies_constraint(
+    v: &AbstractValue,
+    constraint: &TypeExpr,
+    mapping: &Generics<AbstractValue>,
+) -> bool {
+    constraint.admits(v, mapping)
 }
 
 #[cfg(test)]
 mod interpreter_tests {
     use super::*;
     use crate::ast::ASTNode;
+    use crate::diagnostics::Severity;
     use crate::types::{AbstractState, AbstractValue};
 
+    // the number `n`, as the literal `n` evaluates to
+    fn num(n: f64) -> AbstractValue {
+        AbstractValue::number(Interval::constant(n))
+    }
+
+    // the string `text`, as the literal `"text"` evaluates to
+    fn string(text: &str) -> AbstractValue {
+        AbstractValue::StringLiteral(text.to_string())
+    }
+
+    // the integers from `lo` to `hi`
+    fn range(lo: f64, hi: f64) -> AbstractValue {
+        AbstractValue::Interval(Interval {
+            lo,
+            hi,
+            integral: true,
+        })
+    }
+
     #[test]
     fn test_interpret_with_generics_if_statement() {
         let mut state = AbstractState::new();
@@ -345,10 +1515,10 @@ mod interpreter_tests {
         let condition = ASTNode::BinaryOp {
             op: "==".to_string(),
             left: Box::new(ASTNode::Variable("x".to_string())),
-            right: Box::new(ASTNode::Literal(AbstractValue::Number)),
+            right: Box::new(ASTNode::Literal(Literal::Number(42.0))),
         };
-        let then_branch = ASTNode::Literal(AbstractValue::String); // "number"
-        let else_branch = Some(Box::new(ASTNode::Literal(AbstractValue::String))); // "other"
+        let then_branch = ASTNode::StringLiteral("number".to_string());
+        let else_branch = Some(Box::new(ASTNode::StringLiteral("other".to_string())));
 
         let if_statement = ASTNode::IfStatement {
             condition: Box::new(condition),
@@ -357,15 +1527,15 @@ mod interpreter_tests {
         };
 
         // add variable x to state
-        state.assign("x", AbstractValue::Number);
+        state.assign("x", num(42.0));
 
         let result = interpret_with_generics(&if_statement, &mut state, &generic_mapping);
 
-        // condition is true, then-branch is selected which infer to String
+        // condition is true, then-branch is selected
         assert_eq!(
             result,
-            AbstractValue::String,
-            "Expected the result to be 'number' as String"
+            string("number"),
+            "Expected the result to be 'number'"
         );
     }
 
@@ -390,13 +1560,14 @@ mod interpreter_tests {
 
     #[test]
     fn test_generic_function_call() {
-        let mut state = AbstractState::new();
+        let mut state: AbstractState = AbstractState::new();
 
         // function identity<T>(x: T) { return x; }
         let function_identity = ASTNode::FunctionDeclaration {
             name: "identity".to_string(),
-            params: vec!["x".to_string()],
+            params: vec![("x".to_string(), Some(TypeExpr::Named("T".to_string())))],
             generics: vec![("T".to_string(), None)],
+            return_type: None,
             body: Box::new(ASTNode::Variable("x".to_string())),
         };
 
@@ -407,7 +1578,7 @@ mod interpreter_tests {
             target: "y".to_string(),
             value: Box::new(ASTNode::FunctionCall {
                 function: Box::new(ASTNode::Variable("identity".to_string())),
-                arguments: vec![ASTNode::Literal(AbstractValue::Number)],
+                arguments: vec![ASTNode::Literal(Literal::Number(42.0))],
             }),
         };
 
@@ -415,8 +1586,8 @@ mod interpreter_tests {
 
         assert_eq!(
             state.get("y").cloned().unwrap(),
-            AbstractValue::Number,
-            "Expected y to be a Number"
+            num(42.0),
+            "Expected y to be the Number 42"
         );
 
         // z = identity("hello");
@@ -424,7 +1595,7 @@ mod interpreter_tests {
             target: "z".to_string(),
             value: Box::new(ASTNode::FunctionCall {
                 function: Box::new(ASTNode::Variable("identity".to_string())),
-                arguments: vec![ASTNode::Literal(AbstractValue::String)],
+                arguments: vec![ASTNode::StringLiteral("hello".to_string())],
             }),
         };
 
@@ -432,20 +1603,24 @@ mod interpreter_tests {
 
         assert_eq!(
             state.get("z").cloned().unwrap(),
-            AbstractValue::String,
-            "Expected z to be a String"
+            string("hello"),
+            "Expected z to be the String \"hello\""
         );
     }
 
     #[test]
     fn test_bounded_generic_function_call() {
-        let mut state = AbstractState::new();
+        let mut state: AbstractState = AbstractState::new();
 
         // function add<T: Number>(a: T, b: T) { return a + b; }
         let function_add = ASTNode::FunctionDeclaration {
             name: "add".to_string(),
-            params: vec!["a".to_string(), "b".to_string()],
-            generics: vec![("T".to_string(), Some("Number".to_string()))], // generic with constraint
+            params: vec![
+                ("a".to_string(), Some(TypeExpr::Named("T".to_string()))),
+                ("b".to_string(), Some(TypeExpr::Named("T".to_string()))),
+            ],
+            generics: vec![("T".to_string(), Some(TypeExpr::Number))], // generic with constraint
+            return_type: None,
             body: Box::new(ASTNode::BinaryOp {
                 op: "+".to_string(),
                 left: Box::new(ASTNode::Variable("a".to_string())),
@@ -461,8 +1636,8 @@ mod interpreter_tests {
             value: Box::new(ASTNode::FunctionCall {
                 function: Box::new(ASTNode::Variable("add".to_string())),
                 arguments: vec![
-                    ASTNode::Literal(AbstractValue::Number),
-                    ASTNode::Literal(AbstractValue::Number),
+                    ASTNode::Literal(Literal::Number(5.0)),
+                    ASTNode::Literal(Literal::Number(10.0)),
                 ],
             }),
         };
@@ -471,8 +1646,8 @@ mod interpreter_tests {
 
         assert_eq!(
             state.get("result").cloned().unwrap(),
-            AbstractValue::Number,
-            "Expected result to be a Number"
+            num(15.0),
+            "Expected result to be the Number 15"
         );
 
         // invalid_result = add("hello", 10);
@@ -481,8 +1656,8 @@ mod interpreter_tests {
             value: Box::new(ASTNode::FunctionCall {
                 function: Box::new(ASTNode::Variable("add".to_string())),
                 arguments: vec![
-                    ASTNode::Literal(AbstractValue::String),
-                    ASTNode::Literal(AbstractValue::Number),
+                    ASTNode::StringLiteral("hello".to_string()),
+                    ASTNode::Literal(Literal::Number(10.0)),
                 ],
             }),
         };
@@ -491,28 +1666,29 @@ mod interpreter_tests {
 
         assert_eq!(
             state.get("invalid_result").cloned().unwrap(),
-            AbstractValue::Undefined,
-            "Expected invalid_result to be Undefined due to type mismatch"
+            AbstractValue::Top,
+            "Expected invalid_result to be Top due to type mismatch"
         );
     }
 
     #[test]
     fn test_polymorphic_function_with_type_inference() {
-        let mut state = AbstractState::new();
+        let mut state: AbstractState = AbstractState::new();
 
         // function determine_type<T>(x: T) { if (x == 42) { return "number"; } else { return "other"; } }
         let function_determine_type = ASTNode::FunctionDeclaration {
             name: "determine_type".to_string(),
-            params: vec!["x".to_string()],
+            params: vec![("x".to_string(), Some(TypeExpr::Named("T".to_string())))],
             generics: vec![("T".to_string(), None)], // no constraint
+            return_type: None,
             body: Box::new(ASTNode::IfStatement {
                 condition: Box::new(ASTNode::BinaryOp {
                     op: "==".to_string(),
                     left: Box::new(ASTNode::Variable("x".to_string())),
-                    right: Box::new(ASTNode::Literal(AbstractValue::Number)),
+                    right: Box::new(ASTNode::Literal(Literal::Number(42.0))),
                 }),
-                then_branch: Box::new(ASTNode::Literal(AbstractValue::String)), // "number"
-                else_branch: Some(Box::new(ASTNode::Literal(AbstractValue::String))), // "other"
+                then_branch: Box::new(ASTNode::StringLiteral("number".to_string())),
+                else_branch: Some(Box::new(ASTNode::StringLiteral("other".to_string()))),
             }),
         };
 
@@ -524,7 +1700,7 @@ mod interpreter_tests {
             target: "result1".to_string(),
             value: Box::new(ASTNode::FunctionCall {
                 function: Box::new(ASTNode::Variable("determine_type".to_string())),
-                arguments: vec![ASTNode::Literal(AbstractValue::Number)],
+                arguments: vec![ASTNode::Literal(Literal::Number(42.0))],
             }),
         };
 
@@ -532,8 +1708,8 @@ mod interpreter_tests {
 
         assert_eq!(
             state.get("result1").cloned().unwrap(),
-            AbstractValue::String,
-            "Expected result1 to be 'number' as String"
+            string("number"),
+            "Expected result1 to be 'number'"
         );
 
         // call determine_type with a string: result2 = determine_type("hello");
@@ -541,16 +1717,1131 @@ mod interpreter_tests {
             target: "result2".to_string(),
             value: Box::new(ASTNode::FunctionCall {
                 function: Box::new(ASTNode::Variable("determine_type".to_string())),
-                arguments: vec![ASTNode::Literal(AbstractValue::String)],
+                arguments: vec![ASTNode::StringLiteral("hello".to_string())],
             }),
         };
 
         interpret(&call_determine_string, &mut state);
 
+        // `==` converts the string, which is not followed, so either branch may run
         assert_eq!(
             state.get("result2").cloned().unwrap(),
+            string("number").join(&string("other")),
+            "Expected result2 to be 'number' or 'other'"
+        );
+    }
+
+    #[test]
+    fn test_while_loop_reaches_fixpoint() {
+        let mut state = AbstractState::new();
+
+        // x = 1; y = 1; while (c) { y = x; x = "s"; }
+        // `y` only becomes a String on the second iteration
+        let program =
+            crate::parser::parse("c = true; x = 1; y = 1; while (c) { y = x; x = \"s\"; }")
+                .unwrap();
+        interpret(&program, &mut state);
+
+        let expected = AbstractValue::union_of(vec![num(1.0), string("s")]);
+        assert_eq!(state.get("x"), Some(&expected));
+        assert_eq!(state.get("y"), Some(&expected));
+        assert!(state.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_while_loop_widening_terminates() {
+        let mut state = AbstractState::new();
+
+        // x = 1; while (c) { x = [x]; }
+        let program = crate::parser::parse("c = true; x = 1; while (c) { x = [x]; }").unwrap();
+        interpret(&program, &mut state);
+
+        let AbstractValue::Union(variants) = state.get("x").unwrap() else {
+            panic!("expected x to be a union");
+        };
+        assert!(variants.contains(&num(1.0)));
+        assert!(variants
+            .iter()
+            .any(|v| matches!(v, AbstractValue::Array(_))));
+        assert!(state.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_loop_counters_are_bounded() {
+        let program = crate::parser::parse(
+            "i = 0;
+            while (i < 10) { j = i; i = i + 1; }
+            k = 10;
+            while (k > 0) { k = k - 1; }
+            m = 0;
+            while (m < n) { m = m + 1; }",
+        )
+        .unwrap();
+        let mut state = AbstractState::new();
+        state.assign("n", AbstractValue::Number);
+        interpret(&program, &mut state);
+
+        // widened to `[0, +inf]`, then narrowed back by the condition
+        assert_eq!(state.get("j"), Some(&range(0.0, 9.0)));
+        assert_eq!(state.get("i"), Some(&num(10.0)));
+        assert_eq!(state.get("k"), Some(&num(0.0)));
+        // nothing bounds `n`, so nothing bounds `m` from above
+        assert_eq!(state.get("m"), Some(&range(0.0, f64::INFINITY)));
+        assert!(state.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_interval_arithmetic_and_comparisons() {
+        let program = crate::parser::parse(
+            "a = 2 * 3 - 1;
+            b = a / 2;
+            c = 1 / (a - 5);
+            d = -a;
+            if (x < 0) { y = -x; } else { y = x; }
+            if (x === 3) { z = x; } else { z = 0; }",
+        )
+        .unwrap();
+        let mut state = AbstractState::new();
+        state.assign("x", range(-5.0, 5.0));
+        interpret(&program, &mut state);
+
+        assert_eq!(state.get("a"), Some(&num(5.0)));
+        assert_eq!(state.get("b"), Some(&num(2.5)));
+        // the divisor may be 0
+        assert_eq!(state.get("c"), Some(&AbstractValue::Number));
+        assert_eq!(state.get("d"), Some(&num(-5.0)));
+        // `[1, 5]` from the then-branch, `[0, 5]` from the else-branch
+        assert_eq!(state.get("y"), Some(&range(0.0, 5.0)));
+        assert_eq!(state.get("z"), Some(&range(0.0, 3.0)));
+        assert!(state.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_infinite_literals_are_any_number() {
+        // too large for a float, so `Infinity` at runtime, and `Infinity * 0` is `NaN`
+        let program =
+            crate::parser::parse(&format!("x = 1{}; y = x * 0;", "0".repeat(400))).unwrap();
+        let mut state = AbstractState::new();
+        interpret(&program, &mut state);
+
+        assert_eq!(state.get("x"), Some(&AbstractValue::Number));
+        assert_eq!(state.get("y"), Some(&AbstractValue::Number));
+    }
+
+    #[test]
+    fn test_literals_are_folded() {
+        let program = crate::parser::parse(
+            "function compare(x, y) {
+                if (x == y) { return \"equal\"; }
+                return 0;
+            }
+            v = compare(1, 1);
+            w = compare(n, 1);
+            a = \"a\" + 1;
+            b = 1 === \"1\";
+            c = !\"\";
+            s = \"\";
+            while (n < 3) { s = s + \"a\"; }",
+        )
+        .unwrap();
+        let mut state = AbstractState::new();
+        state.assign("n", AbstractValue::Number);
+        interpret(&program, &mut state);
+
+        // equal constants decide the branch
+        assert_eq!(state.get("v"), Some(&string("equal")));
+        let expected = AbstractValue::union_of(vec![num(0.0), string("equal")]);
+        assert_eq!(state.get("w"), Some(&expected));
+        assert_eq!(state.get("a"), Some(&string("a1")));
+        assert_eq!(state.get("b"), Some(&AbstractValue::BooleanLiteral(false)));
+        assert_eq!(state.get("c"), Some(&AbstractValue::BooleanLiteral(true)));
+        // a literal that keeps changing in a loop is widened to its type
+        assert_eq!(state.get("s"), Some(&AbstractValue::String));
+        assert!(state.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_array_reads_are_bounds_checked() {
+        let program = crate::parser::parse(
+            "t = [1, \"s\", true];
+            a = t[1];
+            b = t[3];
+            i = 0;
+            while (i < 2) { c = t[i]; i = i + 1; }
+            d = t[n];
+            if (x) { u = [1]; } else { u = [1, \"s\"]; }
+            e = u[1];",
+        )
+        .unwrap();
+        let mut state = AbstractState::new();
+        state.assign("n", AbstractValue::Number);
+        state.assign("x", AbstractValue::Boolean);
+        interpret(&program, &mut state);
+
+        // a constant index selects its slot, a range the slots it covers
+        assert_eq!(state.get("a"), Some(&string("s")));
+        assert_eq!(state.get("b"), Some(&AbstractValue::Undefined));
+        let expected = AbstractValue::union_of(vec![num(1.0), string("s")]);
+        assert_eq!(state.get("c"), Some(&expected));
+        let expected = AbstractValue::union_of(vec![
+            AbstractValue::Undefined,
+            AbstractValue::BooleanLiteral(true),
+            num(1.0),
+            string("s"),
+        ]);
+        assert_eq!(state.get("d"), Some(&expected));
+        // the shorter array has no second element, which is reported
+        let expected = AbstractValue::union_of(vec![AbstractValue::Undefined, string("s")]);
+        assert_eq!(state.get("e"), Some(&expected));
+        let messages: Vec<_> = state
+            .diagnostics
+            .iter()
+            .map(|d| (d.kind, d.message.as_str()))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                (
+                    DiagnosticKind::IndexOutOfBounds,
+                    "index [3, 3] is out of bounds for an array of length 3"
+                ),
+                (
+                    DiagnosticKind::IndexOutOfBounds,
+                    "index [-inf, +inf] may be out of bounds for an array of length 3"
+                ),
+                (
+                    DiagnosticKind::IndexOutOfBounds,
+                    "index [1, 1] may be out of bounds for an array of length 1 to 2"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_object_literal_and_property_access() {
+        let mut state = AbstractState::new();
+
+        let program = crate::parser::parse(
+            "o = { x: 1, name: \"n\" }; a = o.x; b = o[\"name\"]; o.y = true; o.x = \"s\";",
+        )
+        .unwrap();
+        interpret(&program, &mut state);
+
+        assert_eq!(state.get("a"), Some(&num(1.0)));
+        assert_eq!(state.get("b"), Some(&string("n")));
+
+        let Some(AbstractValue::Object(obj)) = state.get("o") else {
+            panic!("expected o to be an object");
+        };
+        assert_eq!(obj.props.get("x"), Some(&string("s")));
+        assert_eq!(
+            obj.props.get("y"),
+            Some(&AbstractValue::BooleanLiteral(true))
+        );
+        assert!(state.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_nested_property_assignment() {
+        let mut state = AbstractState::new();
+
+        let program =
+            crate::parser::parse("o = { inner: {} }; o.inner.x = 1; v = o.inner.x;").unwrap();
+        interpret(&program, &mut state);
+
+        assert_eq!(state.get("v"), Some(&num(1.0)));
+    }
+
+    #[test]
+    fn test_property_assignment_through_a_missing_property() {
+        let mut state = AbstractState::new();
+
+        let program = crate::parser::parse("o = {}; o.a.b = 1; n = 1; n.a = 2;").unwrap();
+        interpret(&program, &mut state);
+
+        // nothing is written where the property could not be set
+        assert_eq!(
+            state.get("o"),
+            Some(&AbstractValue::Object(AbstractObject {
+                props: BTreeMap::new()
+            }))
+        );
+        assert_eq!(state.get("n"), Some(&num(1.0)));
+        let messages: Vec<_> = state
+            .diagnostics
+            .iter()
+            .map(|d| (d.kind, d.message.as_str()))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                (
+                    DiagnosticKind::NotAnObject,
+                    "cannot set property `b` on Undefined"
+                ),
+                (
+                    DiagnosticKind::NotAnObject,
+                    "cannot set property `a` on Interval([1, 1])"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_missing_property_warns() {
+        let mut state = AbstractState::new();
+
+        let program = crate::parser::parse("o = { x: 1 }; v = o.y;").unwrap();
+        interpret(&program, &mut state);
+
+        assert_eq!(state.get("v"), Some(&AbstractValue::Undefined));
+        assert_eq!(state.diagnostics.len(), 1);
+        assert_eq!(state.diagnostics[0].kind, DiagnosticKind::MissingProperty);
+    }
+
+    #[test]
+    fn test_return_merges_all_paths() {
+        let mut state = AbstractState::new();
+
+        let program = crate::parser::parse(
+            "function f(c) {
+                if (c) { return 1; }
+                x = \"unreachable?\";
+                return \"s\";
+                x = 2;
+            }
+            function g(c) {
+                if (c) { return 1; } else { return 2; }
+                x = 3;
+            }
+            r = f(b);
+            s = g(b);",
+        )
+        .unwrap();
+        state.assign("b", AbstractValue::Boolean);
+        interpret(&program, &mut state);
+
+        let expected = AbstractValue::union_of(vec![num(1.0), string("s")]);
+        assert_eq!(state.get("r"), Some(&expected));
+        assert_eq!(state.get("s"), Some(&range(1.0, 2.0)));
+        let diagnostics: Vec<_> = state
+            .diagnostics
+            .iter()
+            .map(|d| (d.kind, d.location.map(|l| l.line)))
+            .collect();
+        assert_eq!(
+            diagnostics,
+            vec![
+                (DiagnosticKind::UnreachableCode, Some(5)),
+                (DiagnosticKind::UnreachableCode, Some(9)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ruled_out_paths_are_unreachable() {
+        let program = crate::parser::parse(
+            "if (x < 0 && x > 0) { a = \"s\"; } else { a = 1; }
+            i = 0;
+            while (i > 5) { i = i + 1; }
+            function spin() { while (true) { } }
+            b = spin();
+            while (i < 10) { i = i + 1; }
+            c = 1;
+            while (true) { }
+            d = 1;",
+        )
+        .unwrap();
+        let mut state = AbstractState::new();
+        state.assign("x", range(-5.0, 5.0));
+        let analysis = analyze_in(&program, state);
+        let state = &analysis.state;
+
+        // no `x` is both negative and positive, so only the else-branch is taken
+        assert_eq!(state.get("a"), Some(&num(1.0)));
+        // `spin` never returns
+        assert_eq!(state.get("b"), Some(&AbstractValue::Bottom));
+        assert_eq!(state.get("c"), Some(&num(1.0)));
+        // the last loop is never left
+        assert!(state.unreachable);
+        assert_eq!(state.get("d"), None);
+        let diagnostics: Vec<_> = analysis
+            .diagnostics
+            .iter()
+            .map(|d| (d.kind, d.location.map(|l| l.line)))
+            .collect();
+        assert_eq!(
+            diagnostics,
+            vec![(DiagnosticKind::UnreachableCode, Some(3))]
+        );
+    }
+
+    #[test]
+    fn test_loops_are_reported_from_every_state_they_are_reached_in() {
+        // the inner loop is not entered in the first iteration of the outer one,
+        // and the loop of `f` only for the arguments of this call
+        let program = crate::parser::parse(
+            "i = 0;
+            while (i < 10) { j = 0; while (j < i) { j = j + 1; } i = i + 1; }
+            function f(n) { while (n > 0) { n = n - 1; } return n; }
+            r = f(0);",
+        )
+        .unwrap();
+        let analysis = analyze(&program);
+
+        assert_eq!(analysis.state.get("r"), Some(&num(0.0)));
+        assert!(analysis.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_return_falls_off_the_end() {
+        let mut state = AbstractState::new();
+
+        let program = crate::parser::parse(
+            "function f(c) { if (c) { return 1; } }
+            function g() { }
+            r = f(b);
+            u = g();",
+        )
+        .unwrap();
+        state.assign("b", AbstractValue::Boolean);
+        interpret(&program, &mut state);
+
+        let expected = AbstractValue::union_of(vec![num(1.0), AbstractValue::Undefined]);
+        assert_eq!(state.get("r"), Some(&expected));
+        assert_eq!(state.get("u"), Some(&AbstractValue::Undefined));
+    }
+
+    #[test]
+    fn test_return_exits_loop() {
+        let mut state = AbstractState::new();
+
+        // the loop body always returns, so `x` is never reassigned after it
+        let program = crate::parser::parse(
+            "function f(c) {
+                x = 1;
+                while (c) { return x; x = \"s\"; }
+                return true;
+            }
+            r = f(b);",
+        )
+        .unwrap();
+        state.assign("b", AbstractValue::Boolean);
+        interpret(&program, &mut state);
+
+        let expected = AbstractValue::union_of(vec![num(1.0), AbstractValue::BooleanLiteral(true)]);
+        assert_eq!(state.get("r"), Some(&expected));
+    }
+
+    #[test]
+    fn test_analysis_collects_located_diagnostics() {
+        let program = crate::parser::parse(
+            "x = y;
+            arr = [1];
+            e = arr[true];
+            n = 1 - \"s\";
+            r = missing(1);",
+        )
+        .unwrap();
+        let analysis = analyze(&program);
+
+        let found: Vec<_> = analysis
+            .diagnostics
+            .iter()
+            .map(|d| (d.kind, d.severity, d.location.map(|l| l.line)))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (DiagnosticKind::UnknownVariable, Severity::Warning, Some(1)),
+                (DiagnosticKind::NonNumberIndex, Severity::Warning, Some(3)),
+                (DiagnosticKind::InvalidOperand, Severity::Warning, Some(4)),
+                (DiagnosticKind::UnknownFunction, Severity::Error, Some(5)),
+            ]
+        );
+        assert_eq!(analysis.state.get("x"), Some(&AbstractValue::Top));
+    }
+
+    #[test]
+    fn test_constraint_violation_is_reported() {
+        let program = crate::parser::parse(
+            "function add<T: Number>(a: T, b: T) { return a + b; }
+            r = add(\"s\", 1);",
+        )
+        .unwrap();
+        let analysis = analyze(&program);
+
+        assert_eq!(analysis.diagnostics.len(), 1);
+        assert_eq!(
+            analysis.diagnostics[0].kind,
+            DiagnosticKind::ConstraintViolation
+        );
+        assert_eq!(
+            analysis.diagnostics[0].to_string(),
+            "2:13: error: argument String of `add` does not satisfy `T: Number`"
+        );
+    }
+
+    #[test]
+    fn test_unknown_types_are_reported() {
+        let program = crate::parser::parse(
+            "function f<T>(a: Tt) { return a; }
+            function g<T: Nmber>(a: T) { return a; }
+            function h<T>(a: T): T | Nul { return a; }
+            a = f(1);
+            b = g(1);
+            c = h(1);
+            d = f(\"s\");",
+        )
+        .unwrap();
+        let analysis = analyze(&program);
+
+        // annotations and constraints alike, once at the declaration rather than
+        // at each call
+        let messages: Vec<_> = analysis
+            .diagnostics
+            .iter()
+            .map(|d| (d.kind, d.message.as_str()))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                (
+                    DiagnosticKind::UnknownType,
+                    "`a: Tt` of `f` names `Tt`, which is neither a type nor a generic"
+                ),
+                (
+                    DiagnosticKind::UnknownType,
+                    "`T: Nmber` of `g` names `Nmber`, which is neither a type nor a generic"
+                ),
+                (
+                    DiagnosticKind::UnknownType,
+                    "the return type `T | Nul` of `h` names `Nul`, which is neither a type nor a generic"
+                ),
+            ]
+        );
+        // and then ignored
+        assert_eq!(analysis.state.get("a"), Some(&num(1.0)));
+        assert_eq!(analysis.state.get("d"), Some(&string("s")));
+    }
+
+    #[test]
+    fn test_comparison_arithmetic_and_unary_operators() {
+        let program = crate::parser::parse(
+            "a = 1 < 2; b = 1 !== \"s\"; c = 7 % 2; d = 2 ** 3; e = !a; f = -\"5\"; g = typeof f;",
+        )
+        .unwrap();
+        let analysis = analyze(&program);
+        let state = &analysis.state;
+
+        // the operands are known, and so are the results
+        assert_eq!(state.get("a"), Some(&AbstractValue::BooleanLiteral(true)));
+        assert_eq!(state.get("b"), Some(&AbstractValue::BooleanLiteral(true)));
+        assert_eq!(state.get("c"), Some(&AbstractValue::Number));
+        assert_eq!(state.get("d"), Some(&AbstractValue::Number));
+        assert_eq!(state.get("e"), Some(&AbstractValue::BooleanLiteral(false)));
+        assert_eq!(state.get("f"), Some(&AbstractValue::Number));
+        assert_eq!(state.get("g"), Some(&string("number")));
+        assert!(analysis.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_logical_operators_short_circuit() {
+        let program = crate::parser::parse(
+            "o = {};
+            n = null;
+            a = o && 1;
+            b = n && missing;
+            c = n || \"s\";
+            d = x || 1;
+            e = i || \"s\";
+            f = 1 || \"s\";",
+        )
+        .unwrap();
+        let mut state = AbstractState::new();
+        state.assign(
+            "x",
+            AbstractValue::union_of(vec![
+                AbstractValue::Undefined,
+                AbstractValue::Object(AbstractObject {
+                    props: Default::default(),
+                }),
+            ]),
+        );
+        state.assign("i", AbstractValue::Number);
+        interpret(&program, &mut state);
+
+        // an object is always truthy, null always falsy
+        assert_eq!(state.get("a"), Some(&num(1.0)));
+        assert_eq!(state.get("b"), Some(&AbstractValue::Null));
+        assert_eq!(state.get("c"), Some(&string("s")));
+        // only the truthy part (the object) of `x` is kept
+        let expected = AbstractValue::union_of(vec![
+            AbstractValue::Object(AbstractObject {
+                props: Default::default(),
+            }),
+            num(1.0),
+        ]);
+        assert_eq!(state.get("d"), Some(&expected));
+        // a number may be falsy (`0`), so the right operand may be the result
+        let expected = AbstractValue::union_of(vec![AbstractValue::Number, string("s")]);
+        assert_eq!(state.get("e"), Some(&expected));
+        // unless its bounds exclude `0`
+        assert_eq!(state.get("f"), Some(&num(1.0)));
+        // `missing` is never evaluated
+        assert!(state.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_if_narrows_type_tests() {
+        let program = crate::parser::parse(
+            "if (typeof x === \"number\") { a = x; } else { a = true; }
+            if (\"number\" == typeof x) { b = true; } else { b = x; }
+            if (x !== null) { c = x; } else { c = true; }
+            if (x == null) { d = x; } else { d = true; }",
+        )
+        .unwrap();
+        let mut state = AbstractState::new();
+        state.assign(
+            "x",
+            AbstractValue::union_of(vec![
+                AbstractValue::Number,
+                AbstractValue::String,
+                AbstractValue::Null,
+            ]),
+        );
+        interpret(&program, &mut state);
+
+        let expected = AbstractValue::union_of(vec![
+            AbstractValue::Number,
+            AbstractValue::BooleanLiteral(true),
+        ]);
+        assert_eq!(state.get("a"), Some(&expected));
+        // the else-branch gets the complement
+        let expected = AbstractValue::union_of(vec![
+            AbstractValue::BooleanLiteral(true),
+            AbstractValue::String,
+            AbstractValue::Null,
+        ]);
+        assert_eq!(state.get("b"), Some(&expected));
+        let expected = AbstractValue::union_of(vec![
+            AbstractValue::Number,
+            AbstractValue::String,
+            AbstractValue::BooleanLiteral(true),
+        ]);
+        assert_eq!(state.get("c"), Some(&expected));
+        let expected = AbstractValue::union_of(vec![
+            AbstractValue::Null,
+            AbstractValue::BooleanLiteral(true),
+        ]);
+        assert_eq!(state.get("d"), Some(&expected));
+        // the narrowing only applies inside the branches
+        let expected = AbstractValue::union_of(vec![
+            AbstractValue::Number,
             AbstractValue::String,
-            "Expected result2 to be 'other' as String"
+            AbstractValue::Null,
+        ]);
+        assert_eq!(state.get("x"), Some(&expected));
+    }
+
+    #[test]
+    fn test_if_narrows_truthiness_and_arrays() {
+        let program = crate::parser::parse(
+            "if (Array.isArray(y)) { a = y; } else { a = true; }
+            if (y && typeof y !== \"number\") { b = y; } else { b = true; }
+            if (!y) { c = true; } else { c = y; }",
+        )
+        .unwrap();
+        let mut state = AbstractState::new();
+        state.assign(
+            "y",
+            AbstractValue::union_of(vec![
+                AbstractValue::tuple(vec![AbstractValue::Number]),
+                AbstractValue::Null,
+                AbstractValue::Number,
+            ]),
+        );
+        interpret(&program, &mut state);
+
+        let expected = AbstractValue::union_of(vec![
+            AbstractValue::tuple(vec![AbstractValue::Number]),
+            AbstractValue::BooleanLiteral(true),
+        ]);
+        assert_eq!(state.get("a"), Some(&expected));
+        assert_eq!(state.get("b"), Some(&expected));
+        // `null` is never truthy, a number may be
+        let expected = AbstractValue::union_of(vec![
+            AbstractValue::BooleanLiteral(true),
+            AbstractValue::tuple(vec![AbstractValue::Number]),
+            AbstractValue::Number,
+        ]);
+        assert_eq!(state.get("c"), Some(&expected));
+        assert!(state.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_strict_equality_meets_literals() {
+        let program = crate::parser::parse(
+            "if (s === \"a\") { t = s; } else { t = \"b\"; }
+            if (1 === n) { u = n; } else { u = 0; }
+            if (n === \"a\") { v = \"s\"; } else { v = 2; }",
+        )
+        .unwrap();
+        let mut state = AbstractState::new();
+        state.assign("s", AbstractValue::String);
+        state.assign("n", range(0.0, 5.0));
+        interpret(&program, &mut state);
+
+        assert_eq!(state.get("t"), Some(&string("a").join(&string("b"))));
+        assert_eq!(state.get("u"), Some(&range(0.0, 1.0)));
+        // a number is never the string "a"
+        assert_eq!(state.get("v"), Some(&num(2.0)));
+        assert_eq!(state.get("s"), Some(&AbstractValue::String));
+    }
+
+    #[test]
+    fn test_functions_see_their_lexical_scope() {
+        let program = crate::parser::parse(
+            "function read() { return g; }
+            function shadow() { let g = \"s\"; return read(); }
+            function outer(a) {
+                function inner() { return a; }
+                return inner();
+            }
+            g = 1;
+            r1 = read();
+            r2 = shadow();
+            r3 = outer(\"s\");
+            inner();",
+        )
+        .unwrap();
+        let analysis = analyze(&program);
+        let state = &analysis.state;
+
+        // globals are visible in function bodies, even when assigned after the declaration
+        assert_eq!(state.get("r1"), Some(&num(1.0)));
+        // `read` sees the global `g`, not the local of its caller
+        assert_eq!(state.get("r2"), Some(&num(1.0)));
+        // nested functions see the parameters of the enclosing call
+        assert_eq!(state.get("r3"), Some(&string("s")));
+        // `inner` and the locals of a call do not leak into the global scope
+        assert_eq!(state.get("a"), None);
+        let kinds: Vec<_> = analysis.diagnostics.iter().map(|d| d.kind).collect();
+        assert_eq!(kinds, vec![DiagnosticKind::UnknownFunction]);
+    }
+
+    #[test]
+    fn test_functions_assign_to_enclosing_scope() {
+        let program = crate::parser::parse(
+            "flag = null;
+            x = 1;
+            function update(c) {
+                let x = \"s\";
+                if (c) { flag = x; }
+                return x;
+            }
+            r = update(c);",
+        )
+        .unwrap();
+        let mut state = AbstractState::new();
+        state.assign("c", AbstractValue::Boolean);
+        let analysis = analyze_in(&program, state);
+        let state = &analysis.state;
+
+        let expected = AbstractValue::union_of(vec![AbstractValue::Null, string("s")]);
+        assert_eq!(state.get("flag"), Some(&expected));
+        // the declaration shadows the global instead of assigning to it
+        assert_eq!(state.get("x"), Some(&num(1.0)));
+        assert_eq!(state.get("r"), Some(&string("s")));
+        assert!(analysis.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_function_values_and_callbacks() {
+        let program = crate::parser::parse(
+            "function apply(f, x) { return f(x); }
+            function double(n) { return n * 2; }
+            r1 = apply(double, 3);
+            r2 = apply(function (s) { return s + \"!\"; }, \"a\");
+            if (c) { g = double; } else { g = function () { return \"s\"; }; }
+            r3 = g(1);
+            n = 1;
+            r4 = n();",
+        )
+        .unwrap();
+        let mut state = AbstractState::new();
+        state.assign("c", AbstractValue::Boolean);
+        interpret(&program, &mut state);
+
+        assert_eq!(state.get("r1"), Some(&num(6.0)));
+        assert_eq!(state.get("r2"), Some(&string("a!")));
+        // calling a union of functions merges the results of each
+        let expected = AbstractValue::union_of(vec![num(2.0), string("s")]);
+        assert_eq!(state.get("r3"), Some(&expected));
+        assert_eq!(state.get("r4"), Some(&AbstractValue::Top));
+        let diagnostics: Vec<_> = state
+            .diagnostics
+            .iter()
+            .map(|d| (d.kind, d.severity))
+            .collect();
+        assert_eq!(
+            diagnostics,
+            vec![(DiagnosticKind::NotAFunction, Severity::Error)]
         );
     }
+
+    #[test]
+    fn test_closures_capture_their_scope() {
+        let program = crate::parser::parse(
+            "function makeAdder(a) {
+                return function (b) { return a + b; };
+            }
+            add1 = makeAdder(1);
+            r1 = add1(2);
+            r2 = makeAdder(\"s\")(1);
+            a = null;
+            r3 = add1(2);",
+        )
+        .unwrap();
+        let analysis = analyze(&program);
+        let state = &analysis.state;
+
+        assert!(matches!(
+            state.get("add1"),
+            Some(AbstractValue::Function(_))
+        ));
+        assert_eq!(state.get("r1"), Some(&num(3.0)));
+        assert_eq!(state.get("r2"), Some(&string("s1")));
+        // the parameter `a` of the call is captured, not the later global
+        assert_eq!(state.get("r3"), Some(&num(3.0)));
+        assert!(analysis.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_recursive_functions_reach_fixpoint() {
+        let program = crate::parser::parse(
+            "function fact(n) {
+                if (n < 1) { return 1; }
+                return n * fact(n - 1);
+            }
+            function isEven(n) { if (n == 0) { return true; } return isOdd(n - 1); }
+            function isOdd(n) { if (n == 0) { return false; } return isEven(n - 1); }
+            function forever(x) { return forever(x); }
+            r1 = fact(5);
+            r2 = isEven(4);
+            r3 = forever(1);",
+        )
+        .unwrap();
+        for k in 0..3 {
+            let analysis = analyze_with_call_string_length(&program, k);
+            let state = &analysis.state;
+
+            // `n < 1` is decided for the first `k + 1` calls, which multiply
+            // 5, 4, ... by a product that is at least 1
+            let lower = [5.0, 20.0, 60.0][k];
+            assert_eq!(state.get("r1"), Some(&range(lower, f64::INFINITY)));
+            assert_eq!(state.get("r2"), Some(&AbstractValue::Boolean));
+            // never returns
+            assert_eq!(state.get("r3"), Some(&AbstractValue::Bottom));
+            assert!(analysis.diagnostics.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_recursion_on_growing_arguments_terminates() {
+        let program = crate::parser::parse(
+            "function wrap(x, n) {
+                if (n < 1) { return x; }
+                return wrap([x], n - 1);
+            }
+            r = wrap(1, 3);",
+        )
+        .unwrap();
+        let analysis = analyze(&program);
+
+        // `n` is 3 on the first call, so `x` is wrapped at least once
+        assert!(matches!(
+            analysis.state.get("r"),
+            Some(AbstractValue::Array(_))
+        ));
+        assert!(analysis.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_function_summaries_are_reused() {
+        let program = crate::parser::parse(
+            "function add(a, b) { return a + b; }
+            x = add(1, 2);
+            y = add(3, 4);
+            w = add(5, 6);
+            v = add(7, 8);
+            z = add(\"s\", 1);
+            function get() { return g; }
+            g = 1;
+            a = get();
+            b = get();
+            g = \"s\";
+            c = get();",
+        )
+        .unwrap();
+        let analysis = analyze(&program);
+        let state = &analysis.state;
+
+        assert_eq!(state.get("y"), Some(&num(7.0)));
+        // the arguments of the third call with numbers are widened, so the
+        // fourth reuses its summary
+        assert_eq!(state.get("w"), Some(&range(7.0, f64::INFINITY)));
+        assert_eq!(state.get("v"), Some(&range(7.0, f64::INFINITY)));
+        assert_eq!(state.get("z"), Some(&string("s1")));
+        assert_eq!(state.get("b"), Some(&num(1.0)));
+        // the summary of `get` depends on the global it reads
+        assert_eq!(state.get("c"), Some(&string("s")));
+        assert_eq!(analysis.summary_stats, SummaryStats { hits: 2, misses: 6 });
+    }
+
+    #[test]
+    fn test_function_summaries_replay_effects() {
+        let program = crate::parser::parse(
+            "function reset() { count = null; return missing; }
+            count = 1;
+            reset();
+            count = 1;
+            r = reset();",
+        )
+        .unwrap();
+        let analysis = analyze(&program);
+
+        assert_eq!(analysis.summary_stats.hits, 1);
+        assert_eq!(analysis.state.get("count"), Some(&AbstractValue::Null));
+        assert_eq!(analysis.state.get("r"), Some(&AbstractValue::Top));
+        let kinds: Vec<_> = analysis.diagnostics.iter().map(|d| d.kind).collect();
+        assert_eq!(kinds, vec![DiagnosticKind::UnknownVariable]);
+    }
+
+    #[test]
+    fn test_structural_generic_constraints() {
+        let program = crate::parser::parse(
+            "function first<T extends { items: Number[] } | Null>(x: T) { return x; }
+            function pick<T, U extends T>(a: T, b: U) { return b; }
+            a = first({ items: [1, 2] });
+            b = first(null);
+            c = first({ items: [\"s\"] });
+            d = pick(1, 2);
+            e = pick(1, \"s\");
+            function loose<T extends Number>(x) { return x; }
+            f = loose(\"s\");",
+        )
+        .unwrap();
+        let analysis = analyze(&program);
+        let state = &analysis.state;
+
+        assert!(matches!(state.get("a"), Some(AbstractValue::Object(_))));
+        assert_eq!(state.get("b"), Some(&AbstractValue::Null));
+        assert_eq!(state.get("d"), Some(&num(2.0)));
+        // no annotation mentions `T`, which is left unbound and unchecked
+        assert_eq!(state.get("f"), Some(&string("s")));
+        let violations: Vec<_> = analysis
+            .diagnostics
+            .iter()
+            .map(|d| (d.kind, d.location.map(|l| l.line)))
+            .collect();
+        assert_eq!(
+            violations,
+            vec![
+                (DiagnosticKind::ConstraintViolation, Some(5)),
+                (DiagnosticKind::ConstraintViolation, Some(7)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generics_inferred_from_annotations() {
+        let program = crate::parser::parse(
+            "function add<T>(a: T, b: T) { return a + b; }
+            function second<T extends Number, U extends String>(a: U, b: T) { return b; }
+            function head<T>(xs: T[], fallback: T | Null) { return fallback; }
+            function size(xs: Number[]) { return 1; }
+            a = add(1, 2);
+            b = add(1, \"s\");
+            c = second(\"s\", 1);
+            d = head([1, 2], null);
+            e = head([1], \"s\");
+            f = size([\"s\"]);",
+        )
+        .unwrap();
+        let analysis = analyze(&program);
+        let state = &analysis.state;
+
+        assert_eq!(state.get("a"), Some(&num(3.0)));
+        assert_eq!(state.get("b"), Some(&AbstractValue::Top));
+        // `T` and `U` are bound from `b` and `a`, not by position
+        assert_eq!(state.get("c"), Some(&num(1.0)));
+        assert_eq!(state.get("d"), Some(&AbstractValue::Null));
+        let diagnostics: Vec<_> = analysis
+            .diagnostics
+            .iter()
+            .map(|d| (d.kind, d.location.map(|l| l.line)))
+            .collect();
+        assert_eq!(
+            diagnostics,
+            vec![
+                (DiagnosticKind::GenericConflict, Some(6)),
+                (DiagnosticKind::GenericConflict, Some(9)),
+                (DiagnosticKind::TypeMismatch, Some(10)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_return_type_annotations() {
+        let program = crate::parser::parse(
+            "function wrap<T>(x: T): T[] { return [x, x]; }
+            function bad<T>(x: T): T { return \"s\"; }
+            function maybe(x): Number | Null { if (x) { return 1; } }
+            a = wrap(1);
+            b = wrap(\"s\");
+            c = bad(1);
+            d = maybe(false);
+            e = a[1];
+            function make<T>(x): T { return x; }
+            f = make(1);",
+        )
+        .unwrap();
+        let analysis = analyze(&program);
+        let state = &analysis.state;
+
+        // the body's value has the declared type, and knows the length of the array
+        assert_eq!(
+            state.get("a"),
+            Some(&AbstractValue::tuple(vec![num(1.0); 2]))
+        );
+        assert_eq!(state.get("e"), Some(&num(1.0)));
+        assert_eq!(
+            state.get("b"),
+            Some(&AbstractValue::tuple(vec![string("s"); 2]))
+        );
+        // the declared type is the value of a call whose body does not have it
+        assert_eq!(state.get("c"), Some(&AbstractValue::Number));
+        // `T` is reported and may be anything
+        assert_eq!(state.get("f"), Some(&num(1.0)));
+        let diagnostics: Vec<_> = analysis
+            .diagnostics
+            .iter()
+            .map(|d| (d.kind, d.location.map(|l| l.line)))
+            .collect();
+        assert_eq!(
+            diagnostics,
+            vec![
+                (DiagnosticKind::TypeMismatch, Some(6)),
+                (DiagnosticKind::TypeMismatch, Some(7)),
+                (DiagnosticKind::UnboundGeneric, Some(9)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_declared_arrays_have_any_length() {
+        let program = crate::parser::parse(
+            "function id(x): Number[] { return x; }
+            a = id(q);
+            b = a[1];",
+        )
+        .unwrap();
+        let mut state = AbstractState::new();
+        state.assign("q", AbstractValue::Top);
+        interpret(&program, &mut state);
+
+        let numbers = AbstractValue::array_of(Vec::new(), AbstractValue::Number, 0);
+        assert_eq!(state.get("a"), Some(&numbers));
+        let expected =
+            AbstractValue::union_of(vec![AbstractValue::Number, AbstractValue::Undefined]);
+        assert_eq!(state.get("b"), Some(&expected));
+        let messages: Vec<_> = state
+            .diagnostics
+            .iter()
+            .map(|d| d.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            vec!["index [1, 1] may be out of bounds for an array of length at least 0"]
+        );
+    }
+
+    #[test]
+    fn test_bottom_top_and_undefined_are_distinct() {
+        use AbstractValue::*;
+
+        // `Bottom` is the identity of `merge`, `Top` absorbs, `Undefined` is a value
+        assert_eq!(Number.join(&Bottom), Number);
+        assert_eq!(Number.join(&Top), Top);
+        assert_eq!(
+            Number.join(&Undefined),
+            AbstractValue::union_of(vec![Number, Undefined])
+        );
+
+        let program = crate::parser::parse(
+            "if (c) { o = { x: 1 }; } else { o = {}; }
+            a = o.x;
+            b = \"s\" - 1;
+            d = b * 2;",
+        )
+        .unwrap();
+        let mut state = AbstractState::new();
+        state.assign("c", Boolean);
+        interpret(&program, &mut state);
+
+        // the property is missing on one path, where it reads as `undefined`
+        assert_eq!(
+            state.get("a"),
+            Some(&AbstractValue::union_of(vec![num(1.0), Undefined]))
+        );
+        // the analysis gave up on `b`, which is only reported once
+        assert_eq!(state.get("b"), Some(&Top));
+        assert_eq!(state.get("d"), Some(&Top));
+        let kinds: Vec<_> = state.diagnostics.iter().map(|d| d.kind).collect();
+        assert_eq!(kinds, vec![DiagnosticKind::InvalidOperand]);
+    }
+
+    #[test]
+    fn test_unions_are_canonical() {
+        use std::collections::HashSet;
+        use AbstractValue::*;
+
+        let a = String.join(&Null).join(&Number);
+        let b = AbstractValue::union_of(vec![
+            Number,
+            AbstractValue::union_of(vec![String, Null]),
+            Number,
+        ]);
+        assert_eq!(a, b);
+        assert_eq!(format!("{:?}", a), "Union([Null, Number, String])");
+        assert_eq!(HashSet::from([a, b]).len(), 1);
+
+        // arrays combine into one array, whatever the grouping; the shorter
+        // one has no second element
+        let numbers = AbstractValue::tuple(vec![Number]);
+        let mixed = AbstractValue::tuple(vec![Number, String]);
+        let joined = numbers.join(&mixed);
+        assert_eq!(
+            joined,
+            AbstractValue::array_of(vec![Number, String], Bottom, 1)
+        );
+        assert_eq!(format!("{:?}", joined), "Array([Number, String?])");
+        assert_eq!(
+            Null.join(&numbers)
+                .join(&AbstractValue::tuple(vec![String])),
+            AbstractValue::union_of(vec![
+                AbstractValue::tuple(vec![AbstractValue::union_of(vec![String, Number])]),
+                Null
+            ])
+        );
+        assert_ne!(AbstractValue::union_of(vec![Number, String]), Number);
+
+        // literals are absorbed by their type wherever it comes in the union
+        let strings = [string("a"), string("b"), String];
+        for order in [[0, 1, 2], [2, 0, 1], [0, 2, 1]] {
+            let union = AbstractValue::union_of(order.map(|i| strings[i].clone()));
+            assert_eq!(format!("{:?}", union), "String");
+        }
+    }
 }
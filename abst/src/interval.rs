@@ -0,0 +1,361 @@
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+};
+
+// Intervals of numbers, the bounds known for a `Number`.
+//
+// `[lo, hi]` describes the numbers between its bounds, either of which may be
+// infinite. An interval whose values are all integers is `integral`, which
+// keeps strict comparisons exact: `i < 10` bounds an integer `i` by 9. `NaN` is
+// in no interval, so an operation that may produce it gives `None`, any number.
+// Neither are the infinities: an infinite bound means there is none, so a
+// literal too large for a float or an overflow also gives any number.
+//
+// ```
+// i = 0;                  // [0, 0]
+// while (i < 10) {        // [0, 9] in the body, after widening to [0, +inf]
+//     i = i + 1;          // and narrowing back
+// }                       // [10, 10] after the loop
+// ```
+
+#[derive(Clone, Copy)]
+pub struct Interval {
+    pub lo: f64,
+    pub hi: f64,
+    pub integral: bool,
+}
+
+impl Interval {
+    // any number
+    pub const ANY: Interval = Interval {
+        lo: f64::NEG_INFINITY,
+        hi: f64::INFINITY,
+        integral: false,
+    };
+
+    // the number `n`, `None` for an infinity or `NaN`
+    pub fn constant(n: f64) -> Option<Interval> {
+        n.is_finite().then(|| Interval {
+            lo: n,
+            hi: n,
+            integral: n.fract() == 0.0,
+        })
+    }
+
+    // `[lo, hi]`, `None` when it is empty or a bound is `NaN`
+    fn new(lo: f64, hi: f64, integral: bool) -> Option<Interval> {
+        let (lo, hi) = if integral {
+            (lo.ceil(), hi.floor())
+        } else {
+            (lo, hi)
+        };
+        if lo <= hi {
+            // `-0` and `0` are the same bound
+            Some(Interval {
+                lo: lo + 0.0,
+                hi: hi + 0.0,
+                integral,
+            })
+        } else {
+            None
+        }
+    }
+
+    pub fn is_any(&self) -> bool {
+        *self == Interval::ANY
+    }
+
+    // whether every number in `other` is in `self`
+    pub fn contains(&self, other: &Interval) -> bool {
+        self.lo <= other.lo && other.hi <= self.hi && (other.integral || !self.integral)
+    }
+
+    // the smallest interval containing both
+    pub fn hull(&self, other: &Interval) -> Interval {
+        Interval {
+            lo: self.lo.min(other.lo),
+            hi: self.hi.max(other.hi),
+            integral: self.integral && other.integral,
+        }
+    }
+
+    // the numbers in both, `None` when there are none
+    pub fn meet(&self, other: &Interval) -> Option<Interval> {
+        Interval::new(
+            self.lo.max(other.lo),
+            self.hi.min(other.hi),
+            self.integral || other.integral,
+        )
+    }
+
+    // `self` from the previous iteration, widened with `next`: a bound that
+    // keeps moving goes to infinity
+    pub fn widen(&self, next: &Interval) -> Interval {
+        Interval {
+            lo: if next.lo < self.lo {
+                f64::NEG_INFINITY
+            } else {
+                self.lo
+            },
+            hi: if next.hi > self.hi {
+                f64::INFINITY
+            } else {
+                self.hi
+            },
+            integral: self.integral && next.integral,
+        }
+    }
+
+    // `self` narrowed with `next`, a more precise result of the same
+    // iteration: only the infinite bounds are refined
+    pub fn narrow(&self, next: &Interval) -> Interval {
+        Interval {
+            lo: if self.lo == f64::NEG_INFINITY {
+                next.lo
+            } else {
+                self.lo
+            },
+            hi: if self.hi == f64::INFINITY {
+                next.hi
+            } else {
+                self.hi
+            },
+            integral: self.integral || next.integral,
+        }
+    }
+
+    pub fn add(&self, other: &Interval) -> Option<Interval> {
+        Interval::new(
+            bound(self.lo, other.lo, |a, b| a + b)?,
+            bound(self.hi, other.hi, |a, b| a + b)?,
+            self.integral && other.integral,
+        )
+    }
+
+    pub fn subtract(&self, other: &Interval) -> Option<Interval> {
+        Interval::new(
+            bound(self.lo, other.hi, |a, b| a - b)?,
+            bound(self.hi, other.lo, |a, b| a - b)?,
+            self.integral && other.integral,
+        )
+    }
+
+    pub fn multiply(&self, other: &Interval) -> Option<Interval> {
+        // the numbers are finite, so `0 * x` is 0 even without a bound on `x`
+        let product = |a: f64, b: f64| if a == 0.0 || b == 0.0 { 0.0 } else { a * b };
+        let products = [
+            bound(self.lo, other.lo, product)?,
+            bound(self.lo, other.hi, product)?,
+            bound(self.hi, other.lo, product)?,
+            bound(self.hi, other.hi, product)?,
+        ];
+        Interval::new(
+            products.iter().copied().fold(f64::INFINITY, f64::min),
+            products.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            self.integral && other.integral,
+        )
+    }
+
+    pub fn divide(&self, other: &Interval) -> Option<Interval> {
+        // dividing by zero gives an infinity, or `NaN`
+        if other.lo <= 0.0 && 0.0 <= other.hi {
+            return None;
+        }
+        let quotient = |a: f64, b: f64| a / b;
+        let quotients = [
+            bound(self.lo, other.lo, quotient)?,
+            bound(self.lo, other.hi, quotient)?,
+            bound(self.hi, other.lo, quotient)?,
+            bound(self.hi, other.hi, quotient)?,
+        ];
+        if quotients.iter().any(|q| q.is_nan()) {
+            return None;
+        }
+        Interval::new(
+            quotients.iter().copied().fold(f64::INFINITY, f64::min),
+            quotients.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            false,
+        )
+    }
+
+    pub fn negate(&self) -> Interval {
+        Interval {
+            lo: -self.hi,
+            hi: -self.lo,
+            integral: self.integral,
+        }
+    }
+
+    // Whether `self op other` holds for every pair of numbers (`Some(true)`),
+    // for none (`Some(false)`), or depends on them (`None`).
+    pub fn compare(&self, op: &str, other: &Interval) -> Option<bool> {
+        let (always, never) = match op {
+            "<" => (self.hi < other.lo, self.lo >= other.hi),
+            "<=" => (self.hi <= other.lo, self.lo > other.hi),
+            ">" => (self.lo > other.hi, self.hi <= other.lo),
+            ">=" => (self.lo >= other.hi, self.hi < other.lo),
+            "==" | "===" => (
+                self.lo == self.hi && other.lo == other.hi && self.lo == other.lo,
+                self.meet(other).is_none(),
+            ),
+            "!=" | "!==" => return self.compare("==", other).map(|equal| !equal),
+            _ => return None,
+        };
+        if always {
+            Some(true)
+        } else if never {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    // The numbers of `self` for which `self op other` may hold, `None` when there
+    // are none.
+    pub fn refine(&self, op: &str, other: &Interval) -> Option<Interval> {
+        // a bound excluding `bound` itself, exact for integers
+        let below = |bound: f64| {
+            if self.integral && bound.fract() == 0.0 {
+                bound - 1.0
+            } else {
+                bound
+            }
+        };
+        let above = |bound: f64| {
+            if self.integral && bound.fract() == 0.0 {
+                bound + 1.0
+            } else {
+                bound
+            }
+        };
+        match op {
+            "<" => Interval::new(self.lo, self.hi.min(below(other.hi)), self.integral),
+            "<=" => Interval::new(self.lo, self.hi.min(other.hi), self.integral),
+            ">" => Interval::new(self.lo.max(above(other.lo)), self.hi, self.integral),
+            ">=" => Interval::new(self.lo.max(other.lo), self.hi, self.integral),
+            "==" | "===" => self.meet(other),
+            // only a single number can be excluded, and only from a bound
+            "!=" | "!==" if other.lo == other.hi => {
+                let lo = if self.lo == other.lo {
+                    above(self.lo)
+                } else {
+                    self.lo
+                };
+                let hi = if self.hi == other.hi {
+                    below(self.hi)
+                } else {
+                    self.hi
+                };
+                Interval::new(lo, hi, self.integral)
+            }
+            _ => Some(*self),
+        }
+    }
+}
+
+// `op` of two bounds, `None` when finite ones overflow to an infinity
+fn bound(a: f64, b: f64, op: impl Fn(f64, f64) -> f64) -> Option<f64> {
+    let result = op(a, b);
+    (result.is_finite() || a.is_infinite() || b.is_infinite()).then_some(result)
+}
+
+// bounds are compared by value, with `-0 == 0` (see `Interval::new`)
+impl PartialEq for Interval {
+    fn eq(&self, other: &Self) -> bool {
+        self.lo == other.lo && self.hi == other.hi && self.integral == other.integral
+    }
+}
+
+impl Eq for Interval {}
+
+impl Hash for Interval {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.lo + 0.0).to_bits().hash(state);
+        (self.hi + 0.0).to_bits().hash(state);
+        self.integral.hash(state);
+    }
+}
+
+// `[0, 9]` for integers, `[0.0, 9.5]` otherwise
+impl fmt::Debug for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bound = |b: f64| {
+            if b.is_infinite() {
+                format!("{}inf", if b < 0.0 { "-" } else { "+" })
+            } else if self.integral {
+                format!("{}", b)
+            } else {
+                format!("{:?}", b)
+            }
+        };
+        write!(f, "[{}, {}]", bound(self.lo), bound(self.hi))
+    }
+}
+
+#[cfg(test)]
+mod interval_tests {
+    use super::*;
+
+    fn range(lo: f64, hi: f64) -> Interval {
+        Interval::new(lo, hi, true).unwrap()
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let a = range(1.0, 3.0);
+        let b = range(-2.0, 4.0);
+        assert_eq!(a.add(&b), Some(range(-1.0, 7.0)));
+        assert_eq!(a.subtract(&b), Some(range(-3.0, 5.0)));
+        assert_eq!(a.multiply(&b), Some(range(-6.0, 12.0)));
+        // the divisor may be zero
+        assert_eq!(a.divide(&b), None);
+        let half = range(2.0, 2.0).divide(&range(4.0, 4.0)).unwrap();
+        assert_eq!(format!("{:?}", half), "[0.5, 0.5]");
+        let positive = Interval::new(0.0, f64::INFINITY, true).unwrap();
+        assert_eq!(positive.multiply(&range(0.0, 0.0)), Some(range(0.0, 0.0)));
+        assert_eq!(format!("{:?}", positive), "[0, +inf]");
+    }
+
+    #[test]
+    fn test_infinities_are_any_number() {
+        assert_eq!(Interval::constant(f64::INFINITY), None);
+        let huge = range(1e308, 1e308);
+        assert_eq!(huge.add(&huge), None);
+        assert_eq!(huge.negate().subtract(&huge), None);
+        assert_eq!(huge.multiply(&range(10.0, 10.0)), None);
+        assert_eq!(huge.divide(&Interval::new(0.5, 0.5, false).unwrap()), None);
+        // no bound is not an overflow
+        let positive = Interval::new(1.0, f64::INFINITY, true).unwrap();
+        assert_eq!(
+            positive.add(&huge),
+            Interval::new(1e308 + 1.0, f64::INFINITY, true)
+        );
+    }
+
+    #[test]
+    fn test_widen_and_narrow() {
+        let first = range(0.0, 1.0);
+        let widened = first.widen(&range(0.0, 2.0));
+        assert_eq!(widened, Interval::new(0.0, f64::INFINITY, true).unwrap());
+        assert_eq!(widened.narrow(&range(0.0, 10.0)), range(0.0, 10.0));
+        // finite bounds are kept
+        assert_eq!(first.narrow(&range(0.0, 0.0)), first);
+    }
+
+    #[test]
+    fn test_compare_and_refine() {
+        let i = range(0.0, 20.0);
+        let ten = Interval::constant(10.0).unwrap();
+        assert_eq!(i.compare("<", &ten), None);
+        assert_eq!(range(0.0, 9.0).compare("<", &ten), Some(true));
+        assert_eq!(range(10.0, 12.0).compare("<", &ten), Some(false));
+        assert_eq!(i.refine("<", &ten), Some(range(0.0, 9.0)));
+        assert_eq!(i.refine(">=", &ten), Some(range(10.0, 20.0)));
+        assert_eq!(range(0.0, 10.0).refine("!==", &ten), Some(range(0.0, 9.0)));
+        assert_eq!(range(0.0, 9.0).refine(">=", &ten), None);
+        // not integral: the bound itself cannot be excluded
+        let real = Interval::new(0.0, 20.0, false).unwrap();
+        assert_eq!(real.refine("<", &ten), Interval::new(0.0, 10.0, false));
+    }
+}
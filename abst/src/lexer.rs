@@ -0,0 +1,232 @@
+use std::fmt;
+
+use crate::parser::ParseError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    Number(f64),
+    Str(String),
+    Ident(String),
+    // keywords
+    Function,
+    Return,
+    If,
+    Else,
+    While,
+    True,
+    False,
+    Null,
+    Undefined,
+    Let,
+    Var,
+    Const,
+    Typeof,
+    // punctuation
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    Comma,
+    Semicolon,
+    Colon,
+    Dot,
+    Assign,
+    Op(String),
+    Eof,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenKind::Number(n) => write!(f, "number `{}`", n),
+            TokenKind::Str(s) => write!(f, "string {:?}", s),
+            TokenKind::Ident(name) => write!(f, "identifier `{}`", name),
+            TokenKind::Function => write!(f, "`function`"),
+            TokenKind::Return => write!(f, "`return`"),
+            TokenKind::If => write!(f, "`if`"),
+            TokenKind::Else => write!(f, "`else`"),
+            TokenKind::While => write!(f, "`while`"),
+            TokenKind::True => write!(f, "`true`"),
+            TokenKind::False => write!(f, "`false`"),
+            TokenKind::Null => write!(f, "`null`"),
+            TokenKind::Undefined => write!(f, "`undefined`"),
+            TokenKind::Let => write!(f, "`let`"),
+            TokenKind::Var => write!(f, "`var`"),
+            TokenKind::Const => write!(f, "`const`"),
+            TokenKind::Typeof => write!(f, "`typeof`"),
+            TokenKind::LParen => write!(f, "`(`"),
+            TokenKind::RParen => write!(f, "`)`"),
+            TokenKind::LBrace => write!(f, "`{{`"),
+            TokenKind::RBrace => write!(f, "`}}`"),
+            TokenKind::LBracket => write!(f, "`[`"),
+            TokenKind::RBracket => write!(f, "`]`"),
+            TokenKind::Comma => write!(f, "`,`"),
+            TokenKind::Semicolon => write!(f, "`;`"),
+            TokenKind::Colon => write!(f, "`:`"),
+            TokenKind::Dot => write!(f, "`.`"),
+            TokenKind::Assign => write!(f, "`=`"),
+            TokenKind::Op(op) => write!(f, "operator `{}`", op),
+            TokenKind::Eof => write!(f, "end of input"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub line: usize,
+    pub column: usize,
+}
+
+// operators, longest first so that `===` wins over `==` and `=`
+const OPERATORS: [&str; 18] = [
+    "===", "!==", "**", "==", "!=", "<=", ">=", "&&", "||", "+", "-", "*", "/", "%", "<", ">", "!",
+    "|",
+];
+
+// split the source into tokens, tracking 1-based line/column of each token
+pub fn tokenize(source: &str) -> Result<Vec<Token>, ParseError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    let mut line = 1;
+    let mut column = 1;
+
+    while pos < chars.len() {
+        let c = chars[pos];
+        let (start_line, start_column) = (line, column);
+
+        if c == '\n' {
+            pos += 1;
+            line += 1;
+            column = 1;
+            continue;
+        }
+        if c.is_whitespace() {
+            pos += 1;
+            column += 1;
+            continue;
+        }
+        // line comment
+        if c == '/' && chars.get(pos + 1) == Some(&'/') {
+            while pos < chars.len() && chars[pos] != '\n' {
+                pos += 1;
+            }
+            continue;
+        }
+
+        let error = |message: String| ParseError {
+            message,
+            line: start_line,
+            column: start_column,
+            expected: Vec::new(),
+        };
+
+        let start = pos;
+        let kind = if c.is_ascii_digit() {
+            while pos < chars.len() && (chars[pos].is_ascii_digit() || chars[pos] == '.') {
+                pos += 1;
+            }
+            let text: String = chars[start..pos].iter().collect();
+            let n = text
+                .parse::<f64>()
+                .map_err(|_| error(format!("invalid number literal `{}`", text)))?;
+            TokenKind::Number(n)
+        } else if c == '"' || c == '\'' {
+            pos += 1;
+            let mut value = String::new();
+            loop {
+                match chars.get(pos) {
+                    None | Some('\n') => return Err(error("unterminated string literal".into())),
+                    Some(&q) if q == c => break,
+                    Some('\\') => {
+                        let escaped = match chars.get(pos + 1) {
+                            Some('n') => '\n',
+                            Some('t') => '\t',
+                            Some(&other) => other,
+                            None => return Err(error("unterminated string literal".into())),
+                        };
+                        value.push(escaped);
+                        pos += 2;
+                    }
+                    Some(&other) => {
+                        value.push(other);
+                        pos += 1;
+                    }
+                }
+            }
+            pos += 1; // closing quote
+            TokenKind::Str(value)
+        } else if c.is_alphabetic() || c == '_' || c == '$' {
+            while pos < chars.len()
+                && (chars[pos].is_alphanumeric() || chars[pos] == '_' || chars[pos] == '$')
+            {
+                pos += 1;
+            }
+            let text: String = chars[start..pos].iter().collect();
+            match text.as_str() {
+                "function" => TokenKind::Function,
+                "return" => TokenKind::Return,
+                "if" => TokenKind::If,
+                "else" => TokenKind::Else,
+                "while" => TokenKind::While,
+                "true" => TokenKind::True,
+                "false" => TokenKind::False,
+                "null" => TokenKind::Null,
+                "undefined" => TokenKind::Undefined,
+                "let" => TokenKind::Let,
+                "var" => TokenKind::Var,
+                "const" => TokenKind::Const,
+                "typeof" => TokenKind::Typeof,
+                _ => TokenKind::Ident(text),
+            }
+        } else {
+            let single = match c {
+                '(' => Some(TokenKind::LParen),
+                ')' => Some(TokenKind::RParen),
+                '{' => Some(TokenKind::LBrace),
+                '}' => Some(TokenKind::RBrace),
+                '[' => Some(TokenKind::LBracket),
+                ']' => Some(TokenKind::RBracket),
+                ',' => Some(TokenKind::Comma),
+                ';' => Some(TokenKind::Semicolon),
+                ':' => Some(TokenKind::Colon),
+                '.' => Some(TokenKind::Dot),
+                _ => None,
+            };
+            if let Some(kind) = single {
+                pos += 1;
+                kind
+            } else {
+                let rest: String = chars[pos..usize::min(pos + 3, chars.len())]
+                    .iter()
+                    .collect();
+                if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(*op)) {
+                    pos += op.len();
+                    TokenKind::Op(op.to_string())
+                } else if c == '=' {
+                    pos += 1;
+                    TokenKind::Assign
+                } else {
+                    return Err(error(format!("unexpected character `{}`", c)));
+                }
+            }
+        };
+
+        column += pos - start;
+        tokens.push(Token {
+            kind,
+            line: start_line,
+            column: start_column,
+        });
+    }
+
+    tokens.push(Token {
+        kind: TokenKind::Eof,
+        line,
+        column,
+    });
+    Ok(tokens)
+}
@@ -1,118 +1,97 @@
-use ast::ASTNode;
-use interpret::interpret;
+use std::{env, fs, process};
+
+use domain::ValueDomain;
+use interpret::{analyze, analyze_in, analyze_with_call_string_length, Analysis};
+use parser::parse;
+use sign::Sign;
 use types::{AbstractState, AbstractValue};
 
 mod ast;
+mod diagnostics;
+mod domain;
 mod interpret;
+mod interval;
+mod lexer;
+mod narrowing;
+mod parser;
+mod sign;
+mod summary;
+mod type_expr;
 mod types;
-fn main() {
-    let mut state = AbstractState::new();
-
-    // writing a parser to generate AST is unnecessary,
-
-    // function add(a, b) { return a + b; }
-    let function_add = ASTNode::FunctionDeclaration {
-        name: "add".to_string(),
-        params: vec!["a".to_string(), "b".to_string()],
-        generics: vec![],
-        body: Box::new(ASTNode::BinaryOp {
-            op: "+".to_string(),
-            left: Box::new(ASTNode::Variable("a".to_string())),
-            right: Box::new(ASTNode::Variable("b".to_string())),
-        }),
-    };
 
-    // x = 10;
-    let assign_x = ASTNode::Assignment {
-        target: "x".to_string(),
-        value: Box::new(ASTNode::Literal(AbstractValue::Number)),
-    };
+const DEMO_PROGRAM: &str = r#"
+function add(a, b) { return a + b; }
 
-    // y = 20;
-    let assign_y = ASTNode::Assignment {
-        target: "y".to_string(),
-        value: Box::new(ASTNode::Literal(AbstractValue::Number)),
-    };
+x = 10;
+y = 20;
+z = add(x, y);
 
-    // z = add(x, y);
-    let assign_z = ASTNode::Assignment {
-        target: "z".to_string(),
-        value: Box::new(ASTNode::FunctionCall {
-            function: Box::new(ASTNode::Variable("add".to_string())),
-            arguments: vec![
-                ASTNode::Variable("x".to_string()),
-                ASTNode::Variable("y".to_string()),
-            ],
-        }),
-    };
+if (x == y) { w = "equal"; } else { w = 0; }
 
-    // if (x == y) { w = "equal"; } else { w = 0; }
-    let if_statement = ASTNode::IfStatement {
-        condition: Box::new(ASTNode::BinaryOp {
-            op: "==".to_string(),
-            left: Box::new(ASTNode::Variable("x".to_string())),
-            right: Box::new(ASTNode::Variable("y".to_string())),
-        }),
-        then_branch: Box::new(ASTNode::Assignment {
-            target: "w".to_string(),
-            value: Box::new(ASTNode::Literal(AbstractValue::String)),
-        }),
-        else_branch: Some(Box::new(ASTNode::Assignment {
-            target: "w".to_string(),
-            value: Box::new(ASTNode::Literal(AbstractValue::Number)),
-        })),
-    };
+i = 0;
+while (i < 10) { i = i + 1; }
 
-    // while (i < 10) { i = i + 1; }
-    let while_loop = ASTNode::WhileLoop {
-        condition: Box::new(ASTNode::BinaryOp {
-            op: "<".to_string(),
-            left: Box::new(ASTNode::Variable("i".to_string())),
-            right: Box::new(ASTNode::Literal(AbstractValue::Number)),
-        }),
-        body: Box::new(ASTNode::Assignment {
-            target: "i".to_string(),
-            value: Box::new(ASTNode::BinaryOp {
-                op: "+".to_string(),
-                left: Box::new(ASTNode::Variable("i".to_string())),
-                right: Box::new(ASTNode::Literal(AbstractValue::Number)),
-            }),
-        }),
-    };
+arr = [1, "two", true];
+elem = arr[0];
+"#;
 
-    // arr = [1, "two", true];
-    let assign_arr = ASTNode::Assignment {
-        target: "arr".to_string(),
-        value: Box::new(ASTNode::ArrayLiteral(vec![
-            ASTNode::Literal(AbstractValue::Number),
-            ASTNode::Literal(AbstractValue::String),
-            ASTNode::Literal(AbstractValue::Boolean),
-        ])),
-    };
+fn main() {
+    // usage: abst [-k <call string length>] [--signs] [file]
+    let mut args = env::args().skip(1);
+    let mut call_string_length = None;
+    let mut signs = false;
+    let mut path = None;
+    while let Some(arg) = args.next() {
+        if arg == "--signs" {
+            signs = true;
+        } else if arg == "-k" {
+            call_string_length =
+                Some(args.next().and_then(|k| k.parse().ok()).unwrap_or_else(|| {
+                    eprintln!("-k expects a number of call sites");
+                    process::exit(1);
+                }));
+        } else {
+            path = Some(arg);
+        }
+    }
 
-    // elem = arr[0];
-    let assign_elem = ASTNode::Assignment {
-        target: "elem".to_string(),
-        value: Box::new(ASTNode::ArrayIndex {
-            array: Box::new(ASTNode::Variable("arr".to_string())),
-            index: Box::new(ASTNode::Literal(AbstractValue::Number)), // 인덱스는 숫자로 처리
+    // analyze the file given on the command line, or the demo program otherwise
+    let source = match path {
+        Some(path) => fs::read_to_string(&path).unwrap_or_else(|err| {
+            eprintln!("cannot read {}: {}", path, err);
+            process::exit(1);
         }),
+        None => DEMO_PROGRAM.to_string(),
     };
 
-    let program = ASTNode::Block {
-        statements: vec![
-            function_add,
-            assign_x,
-            assign_y,
-            assign_z,
-            if_statement,
-            while_loop,
-            assign_arr,
-            assign_elem,
-        ],
-    };
+    let program = parse(&source).unwrap_or_else(|err| {
+        eprintln!("parse error: {}", err);
+        process::exit(1);
+    });
 
-    interpret(&program, &mut state);
+    // `--signs` analyzes in the sign domain instead of type tags
+    if signs {
+        let state: AbstractState<Sign> = match call_string_length {
+            Some(k) => AbstractState::with_call_string_length(k),
+            None => AbstractState::new(),
+        };
+        report(&analyze_in(&program, state));
+    } else {
+        let analysis = match call_string_length {
+            Some(k) => analyze_with_call_string_length(&program, k),
+            None => analyze(&program),
+        };
+        report(&analysis);
+    }
+}
 
-    println!("Final state: {:?}", state.variables);
+fn report<V: ValueDomain>(analysis: &Analysis<V>) {
+    println!("Final state: {:?}", analysis.state.variables);
+    for diagnostic in &analysis.diagnostics {
+        println!("{}", diagnostic);
+    }
+    println!(
+        "Function summaries: {} reused, {} analyzed",
+        analysis.summary_stats.hits, analysis.summary_stats.misses
+    );
 }
@@ -0,0 +1,326 @@
+use crate::{
+    ast::{ASTNode, Literal},
+    domain::{AbstractDomain, ValueDomain},
+    interval::Interval,
+    AbstractState, AbstractValue,
+};
+
+// Flow-sensitive refinement of variables from branch conditions.
+//
+// When an `if` statement splits control flow, the then-branch may assume the
+// condition was truthy and the else-branch that it was falsy. For conditions
+// that test the type of a variable, this lets us narrow a `Union` down to the
+// variants compatible with the outcome:
+//
+// ```
+// if (typeof x === "number") { /* x: Number */ } else { /* x: rest of x */ }
+// if (x !== undefined) { /* x without Undefined */ }
+// if (x) { /* truthy part of x */ } else { /* falsy part of x */ }
+// if (Array.isArray(x)) { /* arrays in x */ }
+// ```
+//
+// Comparisons of numbers bound them instead (see `Interval::refine`):
+//
+// ```
+// if (i < n) { /* i below the upper bound of n */ } else { /* i at least the lower bound of n */ }
+// if (i === 0) { /* i is 0 */ }
+// ```
+//
+// and strict equality with a literal keeps what both have in common (their `meet`):
+//
+// ```
+// if (s === "a") { /* s is "a" */ }
+// ```
+//
+// Conditions combined with `!`, `&&` and `||` are refined piecewise. A branch
+// that no value can take, like the then-branch of `if (x === null)` for a
+// number `x`, is unreachable (see `AbstractState::unreachable`).
+
+// per-variant outcome of a type test: `Some(b)` when every runtime value of the
+// variant makes the test evaluate to `b`, `None` when it depends on the value
+type TypeTest = fn(&AbstractValue) -> Option<bool>;
+
+// the state in which `condition`, evaluated to `value` in `state`, has the
+// truthiness `assume`, unreachable when the condition rules it out
+pub fn assume<V: ValueDomain>(
+    condition: &ASTNode,
+    value: &V,
+    assume: bool,
+    state: &AbstractState<V>,
+) -> AbstractState<V> {
+    let mut state = state.clone();
+    if value.truthiness() == Some(!assume) {
+        state.unreachable = true;
+    } else {
+        V::refine(condition, assume, &mut state);
+    }
+    state
+}
+
+// narrow `state` assuming `condition` evaluates to `assume`
+pub fn refine(condition: &ASTNode, assume: bool, state: &mut AbstractState) {
+    match condition {
+        ASTNode::UnaryOp { op, operand } if op == "!" => refine(operand, !assume, state),
+        ASTNode::BinaryOp { op, left, right } if op == "&&" || op == "||" => {
+            // `a && b` is truthy iff both are, `a || b` is falsy iff both are
+            if (op == "&&") == assume {
+                refine(left, assume, state);
+                refine(right, assume, state);
+            } else {
+                // either `left` decided the outcome, or `left` did not and `right` did
+                let mut left_state = state.clone();
+                refine(left, assume, &mut left_state);
+                refine(left, !assume, state);
+                refine(right, assume, state);
+                state.merge(&left_state);
+            }
+        }
+        ASTNode::BinaryOp { op, left, right } if is_equality(op) => {
+            // `!==` and `!=` are the negation of `===` and `==`
+            let assume = if op.starts_with('!') { !assume } else { assume };
+            let strict = op.len() == 3;
+            if let Some((name, test)) = equality_test(left, right, strict) {
+                narrow_variable(state, &name, test, assume);
+            } else {
+                if strict && assume {
+                    meet_literal(left, right, state);
+                }
+                let op = if assume { "==" } else { "!=" };
+                refine_comparison(op, left, right, assume, state);
+            }
+        }
+        ASTNode::BinaryOp { op, left, right } if is_comparison(op) => {
+            let op = if assume { op.as_str() } else { negate(op) };
+            refine_comparison(op, left, right, assume, state);
+        }
+        ASTNode::FunctionCall {
+            function,
+            arguments,
+        } if is_array_is_array(function) => {
+            if let [ASTNode::Variable(name)] = arguments.as_slice() {
+                narrow_variable(state, name, is_array, assume);
+            }
+        }
+        ASTNode::Variable(name) => narrow_variable(state, name, AbstractValue::truthiness, assume),
+        _ => {}
+    }
+}
+
+// `Array.isArray`
+pub fn is_array_is_array(function: &ASTNode) -> bool {
+    matches!(
+        function,
+        ASTNode::PropertyAccess { object, property }
+            if property == "isArray" && **object == ASTNode::Variable("Array".to_string())
+    )
+}
+
+fn is_equality(op: &str) -> bool {
+    matches!(op, "===" | "==" | "!==" | "!=")
+}
+
+fn is_comparison(op: &str) -> bool {
+    matches!(op, "<" | "<=" | ">" | ">=")
+}
+
+// the comparison holding exactly when `op` does not, for numbers other than `NaN`
+fn negate(op: &str) -> &'static str {
+    match op {
+        "<" => ">=",
+        "<=" => ">",
+        ">" => "<=",
+        _ => "<",
+    }
+}
+
+// `op` with its operands swapped
+fn flip(op: &str) -> &str {
+    match op {
+        "<" => ">",
+        "<=" => ">=",
+        ">" => "<",
+        ">=" => "<=",
+        _ => op,
+    }
+}
+
+// Bound the numbers of the variables among `left` and `right` assuming
+// `left op right` holds.
+//
+// `NaN` fails every comparison but `!=`, so a number that may be `NaN` (plain
+// `Number`) is bounded only when `op` holds as written (`held`), and not when
+// it is the negation of the condition.
+fn refine_comparison(
+    op: &str,
+    left: &ASTNode,
+    right: &ASTNode,
+    held: bool,
+    state: &mut AbstractState,
+) {
+    let (Some(left_bounds), Some(right_bounds)) = (bounds(left, state), bounds(right, state))
+    else {
+        return;
+    };
+    let held = held && op != "!=";
+    if let ASTNode::Variable(name) = left {
+        bound_variable(state, name, held, |i| i.refine(op, &right_bounds));
+    }
+    if let ASTNode::Variable(name) = right {
+        bound_variable(state, name, held, |i| i.refine(flip(op), &left_bounds));
+    }
+}
+
+// the bounds of an operand that is a number literal or a variable, if it may be a number
+fn bounds(operand: &ASTNode, state: &AbstractState) -> Option<Interval> {
+    let value = match operand {
+        ASTNode::Literal(literal) => return AbstractValue::literal(literal).as_interval(),
+        ASTNode::Variable(name) => state.get(name)?,
+        _ => return None,
+    };
+    let variants = match value {
+        AbstractValue::Union(variants) => variants,
+        _ => std::slice::from_ref(value),
+    };
+    // a canonical union has at most one number
+    variants.iter().find_map(AbstractValue::as_interval)
+}
+
+// replace the numbers of `name` by `refine` of their bounds, dropping those
+// with no number left; `Number` only when `exact`
+fn bound_variable(
+    state: &mut AbstractState,
+    name: &str,
+    exact: bool,
+    refine: impl Fn(&Interval) -> Option<Interval>,
+) {
+    let Some(value) = state.get(name) else {
+        return;
+    };
+    let variants = match value {
+        AbstractValue::Union(variants) => variants.to_vec(),
+        _ => vec![value.clone()],
+    };
+    let narrowed = variants
+        .iter()
+        .filter_map(|v| match v {
+            AbstractValue::Number if !exact => Some(v.clone()),
+            _ => match v.as_interval() {
+                Some(interval) => refine(&interval).map(|i| AbstractValue::number(Some(i))),
+                None => Some(v.clone()),
+            },
+        })
+        .fold(None, |acc: Option<AbstractValue>, v| {
+            Some(match acc {
+                Some(acc) => acc.join(&v),
+                None => v,
+            })
+        });
+    match narrowed {
+        Some(narrowed) => state.assign(name, narrowed),
+        None => state.unreachable = true,
+    }
+}
+
+// `x === literal` holds for the values of `x` that are the literal,
+// with the operands in either order
+fn meet_literal(left: &ASTNode, right: &ASTNode, state: &mut AbstractState) {
+    let (name, literal) = match (left, right) {
+        (ASTNode::Variable(name), literal) | (literal, ASTNode::Variable(name)) => match literal {
+            ASTNode::Literal(literal) => (name, AbstractValue::literal(literal)),
+            ASTNode::StringLiteral(text) => (name, AbstractValue::StringLiteral(text.clone())),
+            _ => return,
+        },
+        _ => return,
+    };
+    let Some(value) = state.get(name) else {
+        return;
+    };
+    match value.meet(&literal) {
+        AbstractValue::Bottom => state.unreachable = true,
+        met => state.assign(name, met),
+    }
+}
+
+// recognize `typeof x === "type"`, `x === null` and `x === undefined`,
+// with the operands in either order
+fn equality_test(left: &ASTNode, right: &ASTNode, strict: bool) -> Option<(String, TypeTest)> {
+    match (left, right) {
+        (ASTNode::UnaryOp { op, operand }, ASTNode::StringLiteral(type_name))
+        | (ASTNode::StringLiteral(type_name), ASTNode::UnaryOp { op, operand })
+            if op == "typeof" =>
+        {
+            let ASTNode::Variable(name) = &**operand else {
+                return None;
+            };
+            let test: TypeTest = match type_name.as_str() {
+                "number" => |v| Some(v.as_interval().is_some()),
+                "string" => |v| {
+                    Some(matches!(
+                        v,
+                        AbstractValue::String | AbstractValue::StringLiteral(_)
+                    ))
+                },
+                "boolean" => |v| {
+                    Some(matches!(
+                        v,
+                        AbstractValue::Boolean | AbstractValue::BooleanLiteral(_)
+                    ))
+                },
+                "undefined" => |v| Some(matches!(v, AbstractValue::Undefined)),
+                "function" => |v| Some(matches!(v, AbstractValue::Function(_))),
+                "object" => |v| {
+                    Some(matches!(
+                        v,
+                        AbstractValue::Null | AbstractValue::Object(_) | AbstractValue::Array(_)
+                    ))
+                },
+                _ => return None,
+            };
+            Some((name.clone(), test))
+        }
+        (ASTNode::Variable(name), ASTNode::Literal(literal))
+        | (ASTNode::Literal(literal), ASTNode::Variable(name)) => {
+            let test: TypeTest = match (literal, strict) {
+                (Literal::Null, true) => |v| Some(matches!(v, AbstractValue::Null)),
+                (Literal::Undefined, true) => |v| Some(matches!(v, AbstractValue::Undefined)),
+                // `x == null` and `x == undefined` hold for both
+                (Literal::Null | Literal::Undefined, false) => {
+                    |v| Some(matches!(v, AbstractValue::Null | AbstractValue::Undefined))
+                }
+                _ => return None,
+            };
+            Some((name.clone(), test))
+        }
+        _ => None,
+    }
+}
+
+fn is_array(v: &AbstractValue) -> Option<bool> {
+    Some(matches!(v, AbstractValue::Array(_)))
+}
+
+// keep the variants of `name` for which `test` may evaluate to `assume`
+fn narrow_variable(state: &mut AbstractState, name: &str, test: TypeTest, assume: bool) {
+    let Some(value) = state.get(name) else {
+        return;
+    };
+    let variants = match value {
+        AbstractValue::Union(variants) => variants.to_vec(),
+        _ => vec![value.clone()],
+    };
+    // nothing is known about `Top`, so it passes every test
+    let narrowed = variants
+        .iter()
+        .filter(|v| **v == AbstractValue::Top || test(v) != Some(!assume))
+        .fold(None, |acc: Option<AbstractValue>, v| {
+            Some(match acc {
+                Some(acc) => acc.join(v),
+                None => v.clone(),
+            })
+        });
+    // no variant can take the branch
+    match narrowed {
+        Some(narrowed) => state.assign(name, narrowed),
+        None => state.unreachable = true,
+    }
+}
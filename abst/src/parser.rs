@@ -0,0 +1,805 @@
+use std::fmt;
+
+use crate::{
+    ast::{ASTNode, Literal, Location},
+    lexer::{tokenize, Token, TokenKind},
+    type_expr::TypeExpr,
+};
+
+// Recursive-descent parser for the JavaScript-like surface syntax.
+//
+// Statements:
+//
+// ```
+// function add<T: Number>(a: T, b: T) { return a + b; }
+// x = 10;
+// let x = 10;             // also `var`/`const`, declares `x` in the current scope
+// if (x == y) { ... } else { ... }
+// while (i < 10) { ... }
+// ```
+//
+// Expressions use the usual JavaScript precedence, from lowest to highest:
+// `||`, `&&`, equality, relational, additive, multiplicative, `**`, unary
+// `!`/`-`/`typeof`, then postfix calls, indexing and property access on top of
+// primary expressions.
+// A `{` at the start of a statement opens a block; elsewhere it is an object
+// literal. Likewise `function` starts a declaration at the start of a statement
+// and a function expression elsewhere.
+//
+// Literals are kept as written (`Literal`), and string literals keep their
+// text: the value of each is up to the domain the program is analyzed in.
+//
+// Every statement is wrapped in `ASTNode::Located` with its line and column.
+//
+// Type annotations on parameters (`a: T`) and return types (`): T[]`), like
+// generic constraints (`<T: Number>`), are kept in the function as `TypeExpr`s.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+    pub expected: Vec<String>,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)?;
+        if !self.expected.is_empty() {
+            write!(f, " (expected {})", self.expected.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+// parse a type expression on its own, as the tests write them
+#[cfg(test)]
+pub fn parse_type(source: &str) -> Result<TypeExpr, ParseError> {
+    let tokens = tokenize(source)?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let ty = parser.parse_type()?;
+    parser.expect(TokenKind::Eof)?;
+    Ok(ty)
+}
+
+// parse a whole program into a `Block` of its top-level statements
+pub fn parse(source: &str) -> Result<ASTNode, ParseError> {
+    let tokens = tokenize(source)?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let mut statements = Vec::new();
+    while parser.peek().kind != TokenKind::Eof {
+        statements.push(parser.parse_statement()?);
+    }
+    Ok(ASTNode::Block { statements })
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos]
+    }
+
+    fn peek_kind(&self) -> &TokenKind {
+        &self.peek().kind
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.pos].clone();
+        if token.kind != TokenKind::Eof {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn error(&self, expected: &[&str]) -> ParseError {
+        let token = self.peek();
+        ParseError {
+            message: format!("unexpected {}", token.kind),
+            line: token.line,
+            column: token.column,
+            expected: expected.iter().map(|e| e.to_string()).collect(),
+        }
+    }
+
+    fn expect(&mut self, kind: TokenKind) -> Result<Token, ParseError> {
+        if *self.peek_kind() == kind {
+            Ok(self.advance())
+        } else {
+            Err(self.error(&[&kind.to_string()]))
+        }
+    }
+
+    fn eat(&mut self, kind: TokenKind) -> bool {
+        if *self.peek_kind() == kind {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_op(&mut self, op: &str) -> bool {
+        if matches!(self.peek_kind(), TokenKind::Op(o) if o == op) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    // contextual keywords (e.g. `extends`) are lexed as identifiers
+    fn eat_contextual(&mut self, keyword: &str) -> bool {
+        if matches!(self.peek_kind(), TokenKind::Ident(name) if name == keyword) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_op(&mut self, op: &str) -> Result<(), ParseError> {
+        if self.eat_op(op) {
+            Ok(())
+        } else {
+            Err(self.error(&[&format!("`{}`", op)]))
+        }
+    }
+
+    fn expect_ident(&mut self) -> Result<String, ParseError> {
+        match self.peek_kind() {
+            TokenKind::Ident(name) => {
+                let name = name.clone();
+                self.advance();
+                Ok(name)
+            }
+            _ => Err(self.error(&["identifier"])),
+        }
+    }
+
+    // semicolons are optional, as with JavaScript's automatic insertion
+    fn end_statement(&mut self) {
+        self.eat(TokenKind::Semicolon);
+    }
+
+    ////////////////////////////////////////////////////////////
+    // statements
+
+    fn parse_statement(&mut self) -> Result<ASTNode, ParseError> {
+        let location = Location {
+            line: self.peek().line,
+            column: self.peek().column,
+        };
+        let node = self.parse_unlocated_statement()?;
+        Ok(ASTNode::Located {
+            location,
+            node: Box::new(node),
+        })
+    }
+
+    fn parse_unlocated_statement(&mut self) -> Result<ASTNode, ParseError> {
+        match self.peek_kind() {
+            TokenKind::Function => self.parse_function(false),
+            TokenKind::If => self.parse_if(),
+            TokenKind::While => {
+                self.advance();
+                self.expect(TokenKind::LParen)?;
+                let condition = self.parse_expression()?;
+                self.expect(TokenKind::RParen)?;
+                let body = self.parse_statement()?;
+                Ok(ASTNode::WhileLoop {
+                    condition: Box::new(condition),
+                    body: Box::new(body),
+                })
+            }
+            TokenKind::LBrace => self.parse_block(),
+            TokenKind::Return => {
+                self.advance();
+                let value = if matches!(
+                    self.peek_kind(),
+                    TokenKind::Semicolon | TokenKind::RBrace | TokenKind::Eof
+                ) {
+                    None
+                } else {
+                    Some(Box::new(self.parse_expression()?))
+                };
+                self.end_statement();
+                Ok(ASTNode::Return(value))
+            }
+            TokenKind::Let | TokenKind::Var | TokenKind::Const => {
+                self.advance();
+                let target = self.expect_ident()?;
+                if self.eat(TokenKind::Colon) {
+                    self.parse_type()?;
+                }
+                let value = if self.eat(TokenKind::Assign) {
+                    self.parse_expression()?
+                } else {
+                    ASTNode::Literal(Literal::Undefined)
+                };
+                self.end_statement();
+                Ok(ASTNode::Declaration {
+                    target,
+                    value: Box::new(value),
+                })
+            }
+            _ => {
+                let start = self.peek().clone();
+                let expr = self.parse_expression()?;
+                let node = if self.eat(TokenKind::Assign) {
+                    let value = Box::new(self.parse_expression()?);
+                    match expr {
+                        ASTNode::Variable(target) => ASTNode::Assignment { target, value },
+                        ASTNode::PropertyAccess { object, property } => {
+                            ASTNode::PropertyAssignment {
+                                object,
+                                property,
+                                value,
+                            }
+                        }
+                        _ => {
+                            return Err(ParseError {
+                                message: "invalid assignment target".to_string(),
+                                line: start.line,
+                                column: start.column,
+                                expected: vec!["identifier".to_string(), "property".to_string()],
+                            })
+                        }
+                    }
+                } else {
+                    expr
+                };
+                self.end_statement();
+                Ok(node)
+            }
+        }
+    }
+
+    fn parse_block(&mut self) -> Result<ASTNode, ParseError> {
+        self.expect(TokenKind::LBrace)?;
+        let mut statements = Vec::new();
+        while !matches!(self.peek_kind(), TokenKind::RBrace | TokenKind::Eof) {
+            statements.push(self.parse_statement()?);
+        }
+        self.expect(TokenKind::RBrace)?;
+        Ok(ASTNode::Block { statements })
+    }
+
+    fn parse_if(&mut self) -> Result<ASTNode, ParseError> {
+        self.expect(TokenKind::If)?;
+        self.expect(TokenKind::LParen)?;
+        let condition = self.parse_expression()?;
+        self.expect(TokenKind::RParen)?;
+        let then_branch = self.parse_statement()?;
+        let else_branch = if self.eat(TokenKind::Else) {
+            Some(Box::new(self.parse_statement()?))
+        } else {
+            None
+        };
+        Ok(ASTNode::IfStatement {
+            condition: Box::new(condition),
+            then_branch: Box::new(then_branch),
+            else_branch,
+        })
+    }
+
+    // function name<T: Constraint, U>(a: T, b: U): T { ... }
+    // a function declaration, or a function expression (where the name is optional)
+    fn parse_function(&mut self, expression: bool) -> Result<ASTNode, ParseError> {
+        self.expect(TokenKind::Function)?;
+        let name = match self.peek_kind() {
+            TokenKind::Ident(_) => Some(self.expect_ident()?),
+            _ if expression => None,
+            _ => return Err(self.error(&["identifier"])),
+        };
+
+        let mut generics = Vec::new();
+        if self.eat_op("<") {
+            loop {
+                let generic = self.expect_ident()?;
+                // both `T: Number` and `T extends Number` are accepted
+                let constraint = if self.eat(TokenKind::Colon) || self.eat_contextual("extends") {
+                    Some(self.parse_type()?)
+                } else {
+                    None
+                };
+                generics.push((generic, constraint));
+                if !self.eat(TokenKind::Comma) {
+                    break;
+                }
+            }
+            self.expect_op(">")?;
+        }
+
+        self.expect(TokenKind::LParen)?;
+        let mut params = Vec::new();
+        while *self.peek_kind() != TokenKind::RParen {
+            let param = self.expect_ident()?;
+            let annotation = if self.eat(TokenKind::Colon) {
+                Some(self.parse_type()?)
+            } else {
+                None
+            };
+            params.push((param, annotation));
+            if !self.eat(TokenKind::Comma) {
+                break;
+            }
+        }
+        self.expect(TokenKind::RParen)?;
+        let return_type = if self.eat(TokenKind::Colon) {
+            Some(self.parse_type()?)
+        } else {
+            None
+        };
+
+        let body = Box::new(self.parse_block()?);
+        match name {
+            Some(name) if !expression => Ok(ASTNode::FunctionDeclaration {
+                name,
+                params,
+                generics,
+                return_type,
+                body,
+            }),
+            _ => Ok(ASTNode::FunctionExpression {
+                name,
+                params,
+                generics,
+                return_type,
+                body,
+            }),
+        }
+    }
+
+    ////////////////////////////////////////////////////////////
+    // type annotations
+
+    // type := postfix ("|" postfix)*
+    // postfix := primary ("[" "]")*
+    // primary := IDENT | "null" | "undefined" | "{" (IDENT ":" type),* "}" | "(" type ")"
+    fn parse_type(&mut self) -> Result<TypeExpr, ParseError> {
+        let mut members = vec![self.parse_postfix_type()?];
+        while self.eat_op("|") {
+            members.push(self.parse_postfix_type()?);
+        }
+        Ok(TypeExpr::union(members))
+    }
+
+    fn parse_postfix_type(&mut self) -> Result<TypeExpr, ParseError> {
+        let mut ty = match self.peek_kind().clone() {
+            TokenKind::Ident(name) => {
+                self.advance();
+                TypeExpr::named(&name)
+            }
+            TokenKind::Null => {
+                self.advance();
+                TypeExpr::Null
+            }
+            TokenKind::Undefined => {
+                self.advance();
+                TypeExpr::Undefined
+            }
+            TokenKind::LBrace => {
+                self.advance();
+                let mut fields = Vec::new();
+                while *self.peek_kind() != TokenKind::RBrace {
+                    let field = self.expect_ident()?;
+                    self.expect(TokenKind::Colon)?;
+                    fields.push((field, self.parse_type()?));
+                    if !self.eat(TokenKind::Comma) {
+                        break;
+                    }
+                }
+                self.expect(TokenKind::RBrace)?;
+                TypeExpr::Object(fields)
+            }
+            TokenKind::LParen => {
+                self.advance();
+                let inner = self.parse_type()?;
+                self.expect(TokenKind::RParen)?;
+                inner
+            }
+            _ => return Err(self.error(&["type"])),
+        };
+        while *self.peek_kind() == TokenKind::LBracket {
+            self.advance();
+            self.expect(TokenKind::RBracket)?;
+            ty = TypeExpr::Array(Box::new(ty));
+        }
+        Ok(ty)
+    }
+
+    ////////////////////////////////////////////////////////////
+    // expressions
+
+    fn parse_expression(&mut self) -> Result<ASTNode, ParseError> {
+        self.parse_binary(0)
+    }
+
+    // precedence climbing over the binary operator table
+    fn parse_binary(&mut self, level: usize) -> Result<ASTNode, ParseError> {
+        const LEVELS: [&[&str]; 6] = [
+            &["||"],
+            &["&&"],
+            &["==", "!=", "===", "!=="],
+            &["<", "<=", ">", ">="],
+            &["+", "-"],
+            &["*", "/", "%"],
+        ];
+        if level == LEVELS.len() {
+            return self.parse_exponent();
+        }
+
+        let mut left = self.parse_binary(level + 1)?;
+        while let TokenKind::Op(op) = self.peek_kind() {
+            if !LEVELS[level].contains(&op.as_str()) {
+                break;
+            }
+            let op = op.clone();
+            self.advance();
+            let right = self.parse_binary(level + 1)?;
+            left = ASTNode::BinaryOp {
+                op,
+                left: Box::new(left),
+                right: Box::new(right),
+            };
+        }
+        Ok(left)
+    }
+
+    // `**` is right-associative
+    fn parse_exponent(&mut self) -> Result<ASTNode, ParseError> {
+        let base = self.parse_unary()?;
+        if self.eat_op("**") {
+            let exponent = self.parse_exponent()?;
+            return Ok(ASTNode::BinaryOp {
+                op: "**".to_string(),
+                left: Box::new(base),
+                right: Box::new(exponent),
+            });
+        }
+        Ok(base)
+    }
+
+    fn parse_unary(&mut self) -> Result<ASTNode, ParseError> {
+        let op = match self.peek_kind() {
+            TokenKind::Op(op) if op == "!" || op == "-" => op.clone(),
+            TokenKind::Typeof => "typeof".to_string(),
+            _ => return self.parse_postfix(),
+        };
+        self.advance();
+        let operand = self.parse_unary()?;
+        Ok(ASTNode::UnaryOp {
+            op,
+            operand: Box::new(operand),
+        })
+    }
+
+    fn parse_postfix(&mut self) -> Result<ASTNode, ParseError> {
+        let mut expr = self.parse_primary()?;
+        loop {
+            match self.peek_kind() {
+                TokenKind::LParen => {
+                    self.advance();
+                    let arguments = self.parse_list(TokenKind::RParen)?;
+                    expr = ASTNode::FunctionCall {
+                        function: Box::new(expr),
+                        arguments,
+                    };
+                }
+                TokenKind::LBracket => {
+                    self.advance();
+                    // `o["x"]` with a literal key is a property access like `o.x`
+                    if let (TokenKind::Str(key), Some(TokenKind::RBracket)) = (
+                        self.peek_kind().clone(),
+                        self.tokens.get(self.pos + 1).map(|t| &t.kind),
+                    ) {
+                        self.advance();
+                        self.advance();
+                        expr = ASTNode::PropertyAccess {
+                            object: Box::new(expr),
+                            property: key,
+                        };
+                        continue;
+                    }
+                    let index = self.parse_expression()?;
+                    self.expect(TokenKind::RBracket)?;
+                    expr = ASTNode::ArrayIndex {
+                        array: Box::new(expr),
+                        index: Box::new(index),
+                    };
+                }
+                TokenKind::Dot => {
+                    self.advance();
+                    let property = self.expect_ident()?;
+                    expr = ASTNode::PropertyAccess {
+                        object: Box::new(expr),
+                        property,
+                    };
+                }
+                _ => return Ok(expr),
+            }
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<ASTNode, ParseError> {
+        let literal = match self.peek_kind() {
+            TokenKind::Number(n) => Some(Literal::Number(*n)),
+            TokenKind::True => Some(Literal::Boolean(true)),
+            TokenKind::False => Some(Literal::Boolean(false)),
+            TokenKind::Null => Some(Literal::Null),
+            TokenKind::Undefined => Some(Literal::Undefined),
+            _ => None,
+        };
+        if let Some(literal) = literal {
+            self.advance();
+            return Ok(ASTNode::Literal(literal));
+        }
+
+        match self.peek_kind().clone() {
+            TokenKind::Str(value) => {
+                self.advance();
+                Ok(ASTNode::StringLiteral(value))
+            }
+            TokenKind::Ident(name) => {
+                self.advance();
+                Ok(ASTNode::Variable(name))
+            }
+            TokenKind::LBracket => {
+                self.advance();
+                let elements = self.parse_list(TokenKind::RBracket)?;
+                Ok(ASTNode::ArrayLiteral(elements))
+            }
+            TokenKind::LBrace => {
+                self.advance();
+                let mut props = Vec::new();
+                while *self.peek_kind() != TokenKind::RBrace {
+                    let key = match self.peek_kind().clone() {
+                        TokenKind::Ident(key) | TokenKind::Str(key) => {
+                            self.advance();
+                            key
+                        }
+                        _ => return Err(self.error(&["property name"])),
+                    };
+                    self.expect(TokenKind::Colon)?;
+                    props.push((key, self.parse_expression()?));
+                    if !self.eat(TokenKind::Comma) {
+                        break;
+                    }
+                }
+                self.expect(TokenKind::RBrace)?;
+                Ok(ASTNode::ObjectLiteral(props))
+            }
+            TokenKind::LParen => {
+                self.advance();
+                let expr = self.parse_expression()?;
+                self.expect(TokenKind::RParen)?;
+                Ok(expr)
+            }
+            TokenKind::Function => self.parse_function(true),
+            _ => Err(self.error(&["expression"])),
+        }
+    }
+
+    // comma separated expressions up to (and including) the closing token
+    fn parse_list(&mut self, close: TokenKind) -> Result<Vec<ASTNode>, ParseError> {
+        let mut items = Vec::new();
+        while *self.peek_kind() != close {
+            items.push(self.parse_expression()?);
+            if !self.eat(TokenKind::Comma) {
+                break;
+            }
+        }
+        self.expect(close)?;
+        Ok(items)
+    }
+}
+
+#[cfg(test)]
+mod parser_tests {
+    use super::*;
+
+    fn var(name: &str) -> Box<ASTNode> {
+        Box::new(ASTNode::Variable(name.to_string()))
+    }
+
+    fn num(n: f64) -> ASTNode {
+        ASTNode::Literal(Literal::Number(n))
+    }
+
+    fn at(line: usize, column: usize, node: ASTNode) -> ASTNode {
+        ASTNode::Located {
+            location: Location { line, column },
+            node: Box::new(node),
+        }
+    }
+
+    fn statements(source: &str) -> Vec<ASTNode> {
+        match parse(source).unwrap() {
+            ASTNode::Block { statements } => statements,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_parse_generic_function() {
+        let program = statements("function add<T: Number>(a: T, b: T) { return a + b; }");
+        let expected = ASTNode::FunctionDeclaration {
+            name: "add".to_string(),
+            params: vec![
+                ("a".to_string(), Some(TypeExpr::Named("T".to_string()))),
+                ("b".to_string(), Some(TypeExpr::Named("T".to_string()))),
+            ],
+            generics: vec![("T".to_string(), Some(TypeExpr::Number))],
+            return_type: None,
+            body: Box::new(ASTNode::Block {
+                statements: vec![at(
+                    1,
+                    39,
+                    ASTNode::Return(Some(Box::new(ASTNode::BinaryOp {
+                        op: "+".to_string(),
+                        left: var("a"),
+                        right: var("b"),
+                    }))),
+                )],
+            }),
+        };
+        assert_eq!(program, vec![at(1, 1, expected)]);
+    }
+
+    #[test]
+    fn test_parse_while_and_precedence() {
+        let program = statements("while (i < 10) { i = i + 1 * 2; }");
+        let expected = ASTNode::WhileLoop {
+            condition: Box::new(ASTNode::BinaryOp {
+                op: "<".to_string(),
+                left: var("i"),
+                right: Box::new(num(10.0)),
+            }),
+            body: Box::new(at(
+                1,
+                16,
+                ASTNode::Block {
+                    statements: vec![at(
+                        1,
+                        18,
+                        ASTNode::Assignment {
+                            target: "i".to_string(),
+                            value: Box::new(ASTNode::BinaryOp {
+                                op: "+".to_string(),
+                                left: var("i"),
+                                right: Box::new(ASTNode::BinaryOp {
+                                    op: "*".to_string(),
+                                    left: Box::new(num(1.0)),
+                                    right: Box::new(num(2.0)),
+                                }),
+                            }),
+                        },
+                    )],
+                },
+            )),
+        };
+        assert_eq!(program, vec![at(1, 1, expected)]);
+    }
+
+    #[test]
+    fn test_parse_array_literal_and_index() {
+        let program = statements("arr = [1, \"two\", true]; elem = arr[0];");
+        let expected = vec![
+            at(
+                1,
+                1,
+                ASTNode::Assignment {
+                    target: "arr".to_string(),
+                    value: Box::new(ASTNode::ArrayLiteral(vec![
+                        num(1.0),
+                        ASTNode::StringLiteral("two".to_string()),
+                        ASTNode::Literal(Literal::Boolean(true)),
+                    ])),
+                },
+            ),
+            at(
+                1,
+                25,
+                ASTNode::Assignment {
+                    target: "elem".to_string(),
+                    value: Box::new(ASTNode::ArrayIndex {
+                        array: var("arr"),
+                        index: Box::new(num(0.0)),
+                    }),
+                },
+            ),
+        ];
+        assert_eq!(program, expected);
+    }
+
+    #[test]
+    fn test_parse_object_literal_and_properties() {
+        let program = statements("o = { x: 1, \"y\": \"s\" }; o.x = o[\"y\"];");
+        let expected = vec![
+            at(
+                1,
+                1,
+                ASTNode::Assignment {
+                    target: "o".to_string(),
+                    value: Box::new(ASTNode::ObjectLiteral(vec![
+                        ("x".to_string(), num(1.0)),
+                        ("y".to_string(), ASTNode::StringLiteral("s".to_string())),
+                    ])),
+                },
+            ),
+            at(
+                1,
+                25,
+                ASTNode::PropertyAssignment {
+                    object: var("o"),
+                    property: "x".to_string(),
+                    value: Box::new(ASTNode::PropertyAccess {
+                        object: var("o"),
+                        property: "y".to_string(),
+                    }),
+                },
+            ),
+        ];
+        assert_eq!(program, expected);
+    }
+
+    #[test]
+    fn test_parse_declaration() {
+        let program = statements("let x: number = y; const z;");
+        let expected = vec![
+            at(
+                1,
+                1,
+                ASTNode::Declaration {
+                    target: "x".to_string(),
+                    value: var("y"),
+                },
+            ),
+            at(
+                1,
+                20,
+                ASTNode::Declaration {
+                    target: "z".to_string(),
+                    value: Box::new(ASTNode::Literal(Literal::Undefined)),
+                },
+            ),
+        ];
+        assert_eq!(program, expected);
+    }
+
+    #[test]
+    fn test_parse_function_expression() {
+        let program = statements("f = function (x) { return x; };");
+        let expected = at(
+            1,
+            1,
+            ASTNode::Assignment {
+                target: "f".to_string(),
+                value: Box::new(ASTNode::FunctionExpression {
+                    name: None,
+                    params: vec![("x".to_string(), None)],
+                    generics: vec![],
+                    return_type: None,
+                    body: Box::new(ASTNode::Block {
+                        statements: vec![at(1, 20, ASTNode::Return(Some(var("x"))))],
+                    }),
+                }),
+            },
+        );
+        assert_eq!(program, vec![expected]);
+
+        // a statement starting with `function` is a declaration, which needs a name
+        assert!(parse("function (x) { }").is_err());
+    }
+
+    #[test]
+    fn test_parse_error_location() {
+        let err = parse("x = 1;\nif (x == ) { }").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 10));
+        assert_eq!(err.expected, vec!["expression".to_string()]);
+    }
+}
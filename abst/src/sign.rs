@@ -0,0 +1,325 @@
+use std::fmt;
+
+use crate::{
+    ast::Literal,
+    domain::{AbstractDomain, ValueDomain},
+};
+
+// The sign domain.
+//
+// A much coarser domain than type tags, mostly to show `interpret` running in
+// another `ValueDomain`: a value is the set of signs a number may have, plus
+// `OTHER` for anything that is not a number (or `NaN`). Arrays, objects and
+// functions are `Top`, like the results of operators used on the wrong kind of
+// operands.
+//
+// ```
+// x = 5;       // POSITIVE
+// y = 0 - x;   // NEGATIVE
+// z = x * y;   // NEGATIVE
+// w = x + y;   // NEGATIVE | ZERO | POSITIVE
+// ```
+//
+// As for intervals, numbers are treated as reals: overflow and underflow are
+// not accounted for.
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Sign {
+    // a set of `NEGATIVE`, `ZERO`, `POSITIVE` and `OTHER` bits
+    Signs(u8),
+    // nothing is known, e.g. after an error
+    Top,
+}
+
+impl Sign {
+    pub const NEGATIVE: Sign = Sign::Signs(1);
+    pub const ZERO: Sign = Sign::Signs(2);
+    pub const POSITIVE: Sign = Sign::Signs(4);
+    pub const OTHER: Sign = Sign::Signs(8);
+    const NUMBERS: Sign = Sign::Signs(7);
+
+    // the signs of the numbers in `lo..=hi`
+    fn within(lo: f64, hi: f64) -> Sign {
+        let mut signs = Sign::bottom();
+        if lo < 0.0 {
+            signs = signs.join(&Sign::NEGATIVE);
+        }
+        if lo <= 0.0 && 0.0 <= hi {
+            signs = signs.join(&Sign::ZERO);
+        }
+        if hi > 0.0 {
+            signs = signs.join(&Sign::POSITIVE);
+        }
+        signs
+    }
+
+    // the single signs among `NEGATIVE`, `ZERO` and `POSITIVE` in `self`
+    fn numbers(&self) -> impl Iterator<Item = Sign> + '_ {
+        [Sign::NEGATIVE, Sign::ZERO, Sign::POSITIVE]
+            .into_iter()
+            .filter(|sign| sign.leq(self))
+    }
+
+    // join of `op` over every pair of single signs of the operands
+    fn combine(&self, other: &Sign, op: fn(Sign, Sign) -> Sign) -> Sign {
+        self.numbers()
+            .flat_map(|a| other.numbers().map(move |b| op(a, b)))
+            .fold(Sign::bottom(), |acc, sign| acc.join(&sign))
+    }
+}
+
+fn negate(a: Sign) -> Sign {
+    match a {
+        Sign::NEGATIVE => Sign::POSITIVE,
+        Sign::POSITIVE => Sign::NEGATIVE,
+        _ => a,
+    }
+}
+
+fn add(a: Sign, b: Sign) -> Sign {
+    if a == Sign::ZERO || a == b {
+        b
+    } else if b == Sign::ZERO {
+        a
+    } else {
+        Sign::NUMBERS
+    }
+}
+
+fn multiply(a: Sign, b: Sign) -> Sign {
+    if a == Sign::ZERO || b == Sign::ZERO {
+        Sign::ZERO
+    } else if a == b {
+        Sign::POSITIVE
+    } else {
+        Sign::NEGATIVE
+    }
+}
+
+fn divide(a: Sign, b: Sign) -> Sign {
+    // `±Infinity`, or `NaN` for `0 / 0`
+    if b == Sign::ZERO {
+        Sign::NUMBERS.join(&Sign::OTHER)
+    } else {
+        multiply(a, b)
+    }
+}
+
+impl fmt::Debug for Sign {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names = [
+            (Sign::NEGATIVE, "Negative"),
+            (Sign::ZERO, "Zero"),
+            (Sign::POSITIVE, "Positive"),
+            (Sign::OTHER, "Other"),
+        ];
+        match self {
+            Sign::Top => write!(f, "Top"),
+            Sign::Signs(0) => write!(f, "Bottom"),
+            _ => {
+                let names: Vec<_> = names
+                    .iter()
+                    .filter(|(sign, _)| sign.leq(self))
+                    .map(|(_, name)| *name)
+                    .collect();
+                write!(f, "{}", names.join(" | "))
+            }
+        }
+    }
+}
+
+impl AbstractDomain for Sign {
+    fn bottom() -> Self {
+        Sign::Signs(0)
+    }
+
+    fn top() -> Self {
+        Sign::Top
+    }
+
+    fn join(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Sign::Signs(a), Sign::Signs(b)) => Sign::Signs(a | b),
+            _ => Sign::Top,
+        }
+    }
+
+    fn meet(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Sign::Signs(a), Sign::Signs(b)) => Sign::Signs(a & b),
+            (Sign::Top, sign) | (sign, Sign::Top) => *sign,
+        }
+    }
+
+    // the domain is finite, so joining is enough for loops to stabilize
+    fn widen(&self, next: &Self) -> Self {
+        self.join(next)
+    }
+
+    fn narrow(&self, next: &Self) -> Self {
+        // never above `self`, whatever `next` is
+        if next.leq(self) {
+            *next
+        } else {
+            *self
+        }
+    }
+
+    fn leq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (_, Sign::Top) => true,
+            (Sign::Top, _) => false,
+            (Sign::Signs(a), Sign::Signs(b)) => a & !b == 0,
+        }
+    }
+}
+
+impl ValueDomain for Sign {
+    fn literal(literal: &Literal) -> Self {
+        match literal {
+            Literal::Number(n) => Sign::within(*n, *n),
+            _ => Sign::OTHER,
+        }
+    }
+
+    fn string_literal(_text: &str) -> Self {
+        Sign::OTHER
+    }
+
+    fn undefined() -> Self {
+        Sign::OTHER
+    }
+
+    fn boolean(_outcome: Option<bool>) -> Self {
+        Sign::OTHER
+    }
+
+    fn binary(op: &str, left: &Self, right: &Self) -> Option<Self> {
+        let arithmetic = matches!(op, "+" | "-" | "*" | "/" | "%" | "**");
+        let comparison = matches!(op, "<" | "<=" | ">" | ">=" | "===" | "==" | "!==" | "!=");
+        if !arithmetic && !comparison {
+            return None;
+        }
+        if *left == Sign::Top || *right == Sign::Top {
+            return Some(Sign::Top);
+        }
+        if *left == Sign::bottom() || *right == Sign::bottom() {
+            return Some(Sign::bottom());
+        }
+        if comparison {
+            return Some(Sign::OTHER);
+        }
+        // arithmetic is defined for numbers, but `+` also concatenates strings
+        if !left.leq(&Sign::NUMBERS) || !right.leq(&Sign::NUMBERS) {
+            return Some(if op == "+" {
+                Sign::NUMBERS.join(&Sign::OTHER)
+            } else {
+                Sign::Top
+            });
+        }
+        Some(match op {
+            "+" => left.combine(right, add),
+            "-" => left.combine(right, |a, b| add(a, negate(b))),
+            "*" => left.combine(right, multiply),
+            "/" => left.combine(right, divide),
+            // `x % 0` is `NaN`, and `**` may underflow to zero
+            _ => Sign::NUMBERS.join(&Sign::OTHER),
+        })
+    }
+
+    fn unary(op: &str, operand: &Self) -> Option<Self> {
+        match op {
+            "-" if operand.leq(&Sign::NUMBERS) => Some(
+                operand
+                    .numbers()
+                    .fold(Sign::bottom(), |acc, sign| acc.join(&negate(sign))),
+            ),
+            // a value that is not a number converts to any number or `NaN`
+            "-" => Some(match operand {
+                Sign::Top => Sign::Top,
+                _ => Sign::NUMBERS.join(&Sign::OTHER),
+            }),
+            "!" | "typeof" => Some(Sign::OTHER),
+            _ => None,
+        }
+    }
+
+    fn truthiness(&self) -> Option<bool> {
+        if *self == Sign::ZERO {
+            Some(false)
+        } else if *self != Sign::bottom() && self.leq(&Sign::NEGATIVE.join(&Sign::POSITIVE)) {
+            Some(true)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod sign_tests {
+    use super::*;
+    use crate::{diagnostics::DiagnosticKind, interpret::analyze_in, parser::parse, AbstractState};
+
+    fn all_signs() -> Vec<Sign> {
+        (0..16).map(Sign::Signs).chain([Sign::Top]).collect()
+    }
+
+    #[test]
+    fn test_lattice_laws() {
+        let signs = all_signs();
+        for a in &signs {
+            assert!(Sign::bottom().leq(a) && a.leq(&Sign::top()));
+            assert_eq!(a.join(a), *a);
+            assert_eq!(a.meet(a), *a);
+            for b in &signs {
+                assert_eq!(a.join(b), b.join(a));
+                assert_eq!(a.meet(b), b.meet(a));
+                assert_eq!(a.leq(b), a.join(b) == *b);
+                assert_eq!(a.leq(b), a.meet(b) == *a);
+                let widened = a.widen(b);
+                assert!(a.leq(&widened) && b.leq(&widened));
+                let narrowed = a.narrow(b);
+                assert!(narrowed.leq(a));
+                if b.leq(a) {
+                    assert!(b.leq(&narrowed));
+                }
+                for c in &signs {
+                    assert_eq!(a.join(b).join(c), a.join(&b.join(c)));
+                    assert_eq!(a.meet(b).meet(c), a.meet(&b.meet(c)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_interpret_in_signs() {
+        let program = parse(
+            "function negate(n) { return 0 - n; }
+            x = 5;
+            y = negate(x);
+            z = x * y;
+            w = x + y;
+            if (y) { a = 1; } else { a = \"s\"; }
+            i = 1;
+            while (i < 100) { i = i * 2; }
+            s = \"a\" + x;
+            t = x * \"b\";",
+        )
+        .unwrap();
+        let analysis = analyze_in(&program, AbstractState::<Sign>::new());
+        let state = &analysis.state;
+
+        assert_eq!(state.get("x"), Some(&Sign::POSITIVE));
+        assert_eq!(state.get("y"), Some(&Sign::NEGATIVE));
+        assert_eq!(state.get("z"), Some(&Sign::NEGATIVE));
+        assert_eq!(state.get("w"), Some(&Sign::NUMBERS));
+        // `y` is never zero, so only the then-branch runs
+        assert_eq!(state.get("a"), Some(&Sign::POSITIVE));
+        assert_eq!(state.get("i"), Some(&Sign::POSITIVE));
+        assert_eq!(state.get("s"), Some(&Sign::NUMBERS.join(&Sign::OTHER)));
+        assert_eq!(state.get("t"), Some(&Sign::Top));
+
+        let kinds: Vec<_> = analysis.diagnostics.iter().map(|d| d.kind).collect();
+        assert_eq!(kinds, vec![DiagnosticKind::InvalidOperand]);
+    }
+}
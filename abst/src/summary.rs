@@ -0,0 +1,345 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::{
+    ast::Location,
+    diagnostics::Diagnostic,
+    domain::ValueDomain,
+    types::{Function, Scope},
+    AbstractValue,
+};
+
+// Summaries of the function calls being analyzed.
+//
+// A call is identified by the function, its context (the last `k` call sites
+// on the call string, innermost last) and the abstract values of its
+// arguments. When a call with the same key is reached again while its body is
+// still being analyzed, the call is recursive: instead of interpreting the body
+// again, the current approximation of its result is used, and the outer call
+// iterates until that approximation is stable.
+//
+// ```
+// function fact(n) {
+//     if (n < 1) { return 1; }
+//     return n * fact(n - 1);   // 1st iteration: `fact(n - 1)` is assumed to be
+// }                             // `Bottom`, 2nd: `Number`, which is stable
+// ```
+//
+// With `k > 0` a recursive function is unrolled up to `k` times before the
+// call sites repeat. A recursive call whose arguments grew since the outer
+// call is analyzed with both widened together, and calls nested deeper than
+// `MAX_CALL_DEPTH` are not analyzed at all, so the analysis always terminates.
+//
+// Once a call has been analyzed, its summary (result, diagnostics and effects
+// on the scopes around the body) is cached along with the names the body
+// looked up outside of its own scope. A later call of the function with the
+// same arguments reuses the summary, whatever the call site, as long as those
+// names still resolve to the same variables and functions. Calls that used the
+// approximate result of an enclosing recursive call are not cached, as that
+// approximation may still change.
+//
+// Once a function has been analyzed `ARGUMENT_WIDENING_DELAY` times with
+// arguments of the same types, in any context, the arguments of its next calls
+// with those types are widened with those of the last one. A function called
+// with ever different numbers is then analyzed a few times, after which its
+// calls reuse a summary:
+//
+// ```
+// x = add(1, 2);   // analyzed with `[1, 1]` and `[2, 2]`
+// y = add(3, 4);   // analyzed with `[3, 3]` and `[4, 4]`
+// z = add(5, 6);   // analyzed with `[3, +inf]` and `[4, +inf]`
+// w = add(7, 8);   // reuses the summary of `z`
+// ```
+
+// default number of call sites distinguishing the contexts of a function
+pub const DEFAULT_CALL_STRING_LENGTH: usize = 1;
+// nested calls deeper than this are not analyzed
+pub const MAX_CALL_DEPTH: usize = 32;
+// iterations for the result of a recursive call to become stable
+pub const MAX_RECURSION_ITERATIONS: usize = 20;
+// calls analyzed with arguments of the same types before the arguments of
+// the next ones are widened
+pub const ARGUMENT_WIDENING_DELAY: usize = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SummaryKey<V = AbstractValue> {
+    pub function: Function,
+    pub context: Vec<Option<Location>>,
+    pub arguments: Vec<V>,
+}
+
+// what a completed call did
+#[derive(Clone)]
+pub struct CallSummary<V = AbstractValue> {
+    pub result: V,
+    pub diagnostics: Vec<Diagnostic>,
+    // the variables the call assigned in the scopes around the body,
+    // by the depth of the scope
+    pub effects: Vec<(usize, String, V)>,
+}
+
+impl<V: ValueDomain> CallSummary<V> {
+    // summary of a call whose body turned the scopes around it from `entry` into `exit`
+    pub fn new(
+        result: V,
+        diagnostics: Vec<Diagnostic>,
+        entry: &[Scope<V>],
+        exit: &[Scope<V>],
+    ) -> Self {
+        let mut effects = Vec::new();
+        for (depth, (before, after)) in entry.iter().zip(exit).enumerate() {
+            for (name, value) in &after.variables {
+                if before.variables.get(name) != Some(value) {
+                    effects.push((depth, name.clone(), value.clone()));
+                }
+            }
+        }
+        CallSummary {
+            result,
+            diagnostics,
+            effects,
+        }
+    }
+}
+
+// how often analyzing a call could be skipped thanks to the cache
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SummaryStats {
+    pub hits: usize,
+    pub misses: usize,
+}
+
+type CacheKey<V> = (Function, Vec<V>);
+
+// what a name looked up by the body resolved to when the call was entered
+#[derive(Clone, PartialEq)]
+struct Dependency<V> {
+    name: String,
+    variable: Option<V>,
+    function: Option<Function>,
+}
+
+// a summary, valid when the names it depends on resolve as they did
+type CachedSummary<V> = (Vec<Dependency<V>>, CallSummary<V>);
+
+// a call being analyzed
+struct ActiveCall<V> {
+    key: SummaryKey<V>,
+    // the scopes around the body when the call was entered
+    scopes: Vec<Scope<V>>,
+    // names looked up outside of the scope of the body
+    reads: BTreeSet<String>,
+    // approximation of the result, `Bottom` when nothing is known yet
+    result: V,
+    // a recursive call has used `result` since it last changed
+    used: bool,
+    // the call used the approximate result of an enclosing call
+    tainted: bool,
+}
+
+pub struct SummaryTable<V = AbstractValue> {
+    // number of call sites kept in a context
+    call_string_length: usize,
+    // call sites of the calls being analyzed, innermost last
+    call_string: Vec<Option<Location>>,
+    // the calls being analyzed, innermost last
+    active: Vec<ActiveCall<V>>,
+    // how many calls were analyzed and the arguments of the last one,
+    // by function and argument types
+    analyzed: HashMap<CacheKey<V>, (usize, Vec<V>)>,
+    cache: HashMap<CacheKey<V>, Vec<CachedSummary<V>>>,
+    stats: SummaryStats,
+}
+
+impl<V: ValueDomain> SummaryTable<V> {
+    pub fn new(call_string_length: usize) -> Self {
+        SummaryTable {
+            call_string_length,
+            call_string: Vec::new(),
+            active: Vec::new(),
+            analyzed: HashMap::new(),
+            cache: HashMap::new(),
+            stats: SummaryStats::default(),
+        }
+    }
+
+    pub fn stats(&self) -> SummaryStats {
+        self.stats
+    }
+
+    // key of a call of `function` at `site`
+    pub fn key(
+        &self,
+        function: &Function,
+        site: Option<Location>,
+        arguments: &[V],
+    ) -> SummaryKey<V> {
+        let mut context = self.call_string.clone();
+        context.push(site);
+        let context = context.split_off(context.len().saturating_sub(self.call_string_length));
+
+        // Widen the arguments with those of an enclosing call in the same context,
+        // so that a recursion on growing arguments reaches that call eventually,
+        // or else with those of an earlier call with arguments of the same
+        // types, unless a call with these very arguments was analyzed already.
+        let mut arguments = arguments.to_vec();
+        let unseen = !self
+            .cache
+            .contains_key(&(function.clone(), arguments.clone()));
+        let previous = self
+            .active
+            .iter()
+            .rev()
+            .map(|call| &call.key)
+            .find(|key| key.function == *function && key.context == context)
+            .map(|key| &key.arguments)
+            .or_else(|| match self.analyzed.get(&types(function, &arguments)) {
+                Some((count, last)) if unseen && *count >= ARGUMENT_WIDENING_DELAY => Some(last),
+                _ => None,
+            });
+        if let Some(previous) = previous {
+            let undefined = V::undefined();
+            let widened: Vec<V> = (0..usize::max(arguments.len(), previous.len()))
+                .map(|i| {
+                    let before = previous.get(i).unwrap_or(&undefined);
+                    let next = arguments.get(i).unwrap_or(&undefined);
+                    before.widen(next)
+                })
+                .collect();
+            let unchanged = widened.len() == previous.len()
+                && widened.iter().zip(previous).all(|(a, b)| a == b);
+            arguments = if unchanged { previous.clone() } else { widened };
+        }
+
+        SummaryKey {
+            function: function.clone(),
+            context,
+            arguments,
+        }
+    }
+
+    // the approximate result of a recursive call, if `key` is being analyzed
+    pub fn recursive_result(&mut self, key: &SummaryKey<V>) -> Option<V> {
+        let position = self.active.iter().rposition(|call| call.key == *key)?;
+        // the calls in between depend on the approximation
+        for call in &mut self.active[position + 1..] {
+            call.tainted = true;
+        }
+        let call = &mut self.active[position];
+        call.used = true;
+        Some(call.result.clone())
+    }
+
+    // a name was looked up outside of the innermost scope,
+    // which every call being analyzed depends on
+    pub fn record_read(&mut self, name: &str) {
+        for call in &mut self.active {
+            if !call.reads.contains(name) {
+                call.reads.insert(name.to_string());
+            }
+        }
+    }
+
+    // the summary of an earlier call of `key` whose body saw the same
+    // values for the names it looked up in `scopes`
+    pub fn cached(&mut self, key: &SummaryKey<V>, scopes: &[Scope<V>]) -> Option<CallSummary<V>> {
+        let summary = self
+            .cache
+            .get(&(key.function.clone(), key.arguments.clone()))
+            .and_then(|entries| {
+                entries.iter().find(|(dependencies, _)| {
+                    dependencies
+                        .iter()
+                        .all(|dependency| *dependency == resolve(&dependency.name, scopes))
+                })
+            })
+            .map(|(_, summary)| summary.clone());
+        if summary.is_some() {
+            self.stats.hits += 1;
+        } else {
+            self.stats.misses += 1;
+        }
+        summary
+    }
+
+    pub fn depth(&self) -> usize {
+        self.active.len()
+    }
+
+    // start analyzing the call of `key` at `site`, whose body sees `scopes`
+    pub fn enter(&mut self, key: &SummaryKey<V>, site: Option<Location>, scopes: &[Scope<V>]) {
+        self.call_string.push(site);
+        self.active.push(ActiveCall {
+            key: key.clone(),
+            scopes: scopes.to_vec(),
+            reads: BTreeSet::new(),
+            result: V::bottom(),
+            used: false,
+            tainted: false,
+        });
+    }
+
+    // Record `result` for one iteration over the body of the innermost call,
+    // returning whether it is final: no recursive call has used an
+    // approximation smaller than it.
+    pub fn update(&mut self, result: &V) -> bool {
+        let call = self
+            .active
+            .last_mut()
+            .expect("summaries are updated while their call is analyzed");
+        let stable = !call.used || result.leq(&call.result);
+        let widened = call.result.widen(result);
+        call.result = widened;
+        call.used = false;
+        stable
+    }
+
+    // finish analyzing the innermost call, which did what `summary` says
+    pub fn exit(&mut self, summary: CallSummary<V>) {
+        self.call_string.pop();
+        let call = self
+            .active
+            .pop()
+            .expect("calls are exited after being entered");
+        let analyzed = self
+            .analyzed
+            .entry(types(&call.key.function, &call.key.arguments))
+            .or_insert((0, Vec::new()));
+        *analyzed = (analyzed.0 + 1, call.key.arguments.clone());
+        if !call.tainted {
+            let dependencies = call
+                .reads
+                .iter()
+                .map(|name| resolve(name, &call.scopes))
+                .collect();
+            self.cache
+                .entry((call.key.function, call.key.arguments))
+                .or_default()
+                .push((dependencies, summary));
+        }
+    }
+}
+
+// `function` with the types of `arguments`
+fn types<V: ValueDomain>(function: &Function, arguments: &[V]) -> CacheKey<V> {
+    (
+        function.clone(),
+        arguments.iter().map(V::base_type).collect(),
+    )
+}
+
+// what `name` refers to in `scopes`, innermost first
+fn resolve<V: Clone>(name: &str, scopes: &[Scope<V>]) -> Dependency<V> {
+    Dependency {
+        name: name.to_string(),
+        variable: scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.variables.get(name))
+            .cloned(),
+        function: scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.functions.get(name))
+            .cloned(),
+    }
+}
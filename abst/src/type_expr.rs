@@ -0,0 +1,493 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{domain::AbstractDomain, types::AbstractObject, AbstractValue};
+
+// Type expressions, as written in generic constraints (`<T extends Number[]>`).
+//
+// ```
+// Number | String        // either
+// Number[]               // an array whose elements are all numbers
+// { x: Number }          // an object with (at least) a property `x`
+// Null, Undefined
+// T                      // the value bound to another generic
+// ```
+//
+// A value satisfies a type when every runtime value it describes does, so a
+// `Union` value has to satisfy the type with each of its variants.
+//
+// Parameter annotations (`(a: T, b: T[])`) also use type expressions, and the
+// generics they mention are inferred by unifying each annotation with the
+// value of its argument. A return type (`): T[]`) is then checked against
+// the value returned by the body, and instantiated with the inferred generics
+// to give the value of the call when the body's value does not have it.
+
+// why an argument does not fit the annotation of its parameter
+#[derive(Debug, Clone, PartialEq)]
+pub enum UnifyError {
+    // the value does not have the type
+    Mismatch,
+    // `generic` was already inferred as `bound` from another argument
+    Conflict {
+        generic: String,
+        bound: Box<AbstractValue>,
+        found: Box<AbstractValue>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TypeExpr {
+    Number,
+    String,
+    Boolean,
+    Null,
+    Undefined,
+    Array(Box<TypeExpr>),
+    Object(Vec<(String, TypeExpr)>),
+    Union(Vec<TypeExpr>),
+    // another generic parameter (or a name we do not know)
+    Named(String),
+}
+
+impl TypeExpr {
+    // the type named `name`, for both `Number` and TypeScript's `number`
+    pub fn named(name: &str) -> TypeExpr {
+        match name {
+            "Number" | "number" => TypeExpr::Number,
+            "String" | "string" => TypeExpr::String,
+            "Boolean" | "boolean" => TypeExpr::Boolean,
+            "Null" => TypeExpr::Null,
+            "Undefined" => TypeExpr::Undefined,
+            _ => TypeExpr::Named(name.to_string()),
+        }
+    }
+
+    // `a | b`, flattening nested unions
+    pub fn union(types: Vec<TypeExpr>) -> TypeExpr {
+        let mut members = Vec::new();
+        for ty in types {
+            match ty {
+                TypeExpr::Union(inner) => members.extend(inner),
+                _ => members.push(ty),
+            }
+        }
+        if members.len() == 1 {
+            members.pop().unwrap()
+        } else {
+            TypeExpr::Union(members)
+        }
+    }
+
+    // the most precise type describing `value`, if there is one
+    pub fn of(value: &AbstractValue) -> Option<TypeExpr> {
+        Some(match value {
+            AbstractValue::Number | AbstractValue::Interval(_) => TypeExpr::Number,
+            AbstractValue::String | AbstractValue::StringLiteral(_) => TypeExpr::String,
+            AbstractValue::Boolean | AbstractValue::BooleanLiteral(_) => TypeExpr::Boolean,
+            AbstractValue::Null => TypeExpr::Null,
+            AbstractValue::Undefined => TypeExpr::Undefined,
+            AbstractValue::Array(array) => {
+                let elements = array
+                    .elements()
+                    .iter()
+                    .chain(Some(array.rest()).filter(|rest| **rest != AbstractValue::Bottom))
+                    .map(TypeExpr::of)
+                    .collect::<Option<Vec<_>>>()?;
+                TypeExpr::Array(Box::new(TypeExpr::union(elements)))
+            }
+            AbstractValue::Object(obj) => TypeExpr::Object(
+                obj.props
+                    .iter()
+                    .map(|(k, v)| Some((k.clone(), TypeExpr::of(v)?)))
+                    .collect::<Option<_>>()?,
+            ),
+            AbstractValue::Union(variants) => TypeExpr::union(
+                variants
+                    .iter()
+                    .map(TypeExpr::of)
+                    .collect::<Option<Vec<_>>>()?,
+            ),
+            AbstractValue::Generic(_, concrete) => TypeExpr::of(concrete)?,
+            AbstractValue::Function(_) | AbstractValue::Bottom | AbstractValue::Top => return None,
+        })
+    }
+
+    // Whether every runtime value described by `value` has this type.
+    //
+    // `generics` holds the values bound to the generics of the function, for
+    // constraints referring to another generic.
+    pub fn admits(
+        &self,
+        value: &AbstractValue,
+        generics: &HashMap<String, Box<AbstractValue>>,
+    ) -> bool {
+        match (self, value) {
+            // no value is wrongly typed, and one we know nothing about is not reported
+            (_, AbstractValue::Bottom | AbstractValue::Top) => true,
+            (_, AbstractValue::Union(variants)) => {
+                variants.iter().all(|v| self.admits(v, generics))
+            }
+            (_, AbstractValue::Generic(_, concrete)) => self.admits(concrete, generics),
+            (TypeExpr::Union(members), _) => members.iter().any(|ty| ty.admits(value, generics)),
+            // a generic bound to `Top` may be anything
+            (TypeExpr::Named(name), _) => generics.get(name).is_some_and(|bound| {
+                **bound == AbstractValue::Top
+                    || TypeExpr::of(bound).is_some_and(|ty| ty.admits(value, generics))
+            }),
+            (TypeExpr::Number, AbstractValue::Number | AbstractValue::Interval(_))
+            | (TypeExpr::String, AbstractValue::String | AbstractValue::StringLiteral(_))
+            | (TypeExpr::Boolean, AbstractValue::Boolean | AbstractValue::BooleanLiteral(_))
+            | (TypeExpr::Null, AbstractValue::Null)
+            | (TypeExpr::Undefined, AbstractValue::Undefined) => true,
+            (TypeExpr::Array(element), AbstractValue::Array(array)) => array
+                .elements()
+                .iter()
+                .chain([array.rest()])
+                .all(|e| element.admits(e, generics)),
+            (TypeExpr::Object(fields), AbstractValue::Object(obj)) => fields
+                .iter()
+                .all(|(name, ty)| obj.props.get(name).is_some_and(|v| ty.admits(v, generics))),
+            _ => false,
+        }
+    }
+
+    // Infer the generics of `self`, among `generics`, from `value`, adding them
+    // to `bindings`. Like in `admits`, a name that is neither a generic nor a
+    // known type matches nothing (see `unknown_name`).
+    pub fn unify(
+        &self,
+        value: &AbstractValue,
+        generics: &[String],
+        bindings: &mut HashMap<String, Box<AbstractValue>>,
+    ) -> Result<(), UnifyError> {
+        match value {
+            AbstractValue::Generic(_, concrete) => return self.unify(concrete, generics, bindings),
+            // nothing to infer from
+            AbstractValue::Bottom | AbstractValue::Top => return Ok(()),
+            _ => {}
+        }
+        match self {
+            // a generic stands for a type, not for the bounds of numbers
+            TypeExpr::Named(name) if generics.contains(name) => match bindings.get(name) {
+                Some(bound) if **bound == value.base_type() => Ok(()),
+                Some(bound) => Err(UnifyError::Conflict {
+                    generic: name.clone(),
+                    bound: bound.clone(),
+                    found: Box::new(value.base_type()),
+                }),
+                None => {
+                    bindings.insert(name.clone(), Box::new(value.base_type()));
+                    Ok(())
+                }
+            },
+            TypeExpr::Named(_) => Err(UnifyError::Mismatch),
+            TypeExpr::Array(element) => {
+                // every element of every array variant has the element type
+                let mut elements = Vec::new();
+                for variant in variants(value) {
+                    match variant {
+                        AbstractValue::Array(array) => {
+                            elements.extend(array.elements().iter().chain([array.rest()]))
+                        }
+                        _ => return Err(UnifyError::Mismatch),
+                    }
+                }
+                match join(elements) {
+                    Some(elements) => element.unify(&elements, generics, bindings),
+                    None => Ok(()),
+                }
+            }
+            TypeExpr::Object(fields) => {
+                let AbstractValue::Object(obj) = value else {
+                    return Err(UnifyError::Mismatch);
+                };
+                for (name, ty) in fields {
+                    let field = obj.props.get(name).ok_or(UnifyError::Mismatch)?;
+                    ty.unify(field, generics, bindings)?;
+                }
+                Ok(())
+            }
+            TypeExpr::Union(members) => {
+                // the variants no concrete member admits are left to the generic one,
+                // so `x: T | Null` with `Number | Null` infers `T = Number`
+                let (open, closed): (Vec<&TypeExpr>, Vec<&TypeExpr>) =
+                    members.iter().partition(|ty| ty.mentions(generics));
+                let rest = join(
+                    variants(value)
+                        .into_iter()
+                        .filter(|v| !closed.iter().any(|ty| ty.admits(v, bindings)))
+                        .collect(),
+                );
+                match (rest, open.as_slice()) {
+                    (None, _) => Ok(()),
+                    (Some(rest), [ty]) => ty.unify(&rest, generics, bindings),
+                    (Some(rest), _) => {
+                        // the first generic member the rest unifies with
+                        for ty in open {
+                            let mut attempt = bindings.clone();
+                            if ty.unify(&rest, generics, &mut attempt).is_ok() {
+                                *bindings = attempt;
+                                return Ok(());
+                            }
+                        }
+                        Err(UnifyError::Mismatch)
+                    }
+                }
+            }
+            _ if self.admits(value, bindings) => Ok(()),
+            _ => Err(UnifyError::Mismatch),
+        }
+    }
+
+    // The value of this type, with the generics replaced by their values in
+    // `generics`. `None` when it mentions a name that is not bound.
+    //
+    // An array type is an array of any length, whose every slot has the type
+    // of the elements.
+    pub fn instantiate(
+        &self,
+        generics: &HashMap<String, Box<AbstractValue>>,
+    ) -> Option<AbstractValue> {
+        Some(match self {
+            TypeExpr::Number => AbstractValue::Number,
+            TypeExpr::String => AbstractValue::String,
+            TypeExpr::Boolean => AbstractValue::Boolean,
+            TypeExpr::Null => AbstractValue::Null,
+            TypeExpr::Undefined => AbstractValue::Undefined,
+            TypeExpr::Array(element) => {
+                AbstractValue::array_of(Vec::new(), element.instantiate(generics)?, 0)
+            }
+            TypeExpr::Object(fields) => AbstractValue::Object(AbstractObject {
+                props: fields
+                    .iter()
+                    .map(|(name, ty)| Some((name.clone(), ty.instantiate(generics)?)))
+                    .collect::<Option<_>>()?,
+            }),
+            TypeExpr::Union(members) => {
+                let members = members
+                    .iter()
+                    .map(|ty| ty.instantiate(generics))
+                    .collect::<Option<Vec<_>>>()?;
+                join(members.iter().collect())?
+            }
+            TypeExpr::Named(name) => (**generics.get(name)?).clone(),
+        })
+    }
+
+    // the first name in the type that is not one of `generics`, e.g. a misspelled one
+    pub fn unknown_name(&self, generics: &[String]) -> Option<&str> {
+        match self {
+            TypeExpr::Named(name) if !generics.contains(name) => Some(name),
+            TypeExpr::Array(element) => element.unknown_name(generics),
+            TypeExpr::Object(fields) => fields.iter().find_map(|(_, ty)| ty.unknown_name(generics)),
+            TypeExpr::Union(members) => members.iter().find_map(|ty| ty.unknown_name(generics)),
+            _ => None,
+        }
+    }
+
+    // whether any of `generics` appears in the type
+    pub fn mentions(&self, generics: &[String]) -> bool {
+        match self {
+            TypeExpr::Named(name) => generics.contains(name),
+            TypeExpr::Array(element) => element.mentions(generics),
+            TypeExpr::Object(fields) => fields.iter().any(|(_, ty)| ty.mentions(generics)),
+            TypeExpr::Union(members) => members.iter().any(|ty| ty.mentions(generics)),
+            _ => false,
+        }
+    }
+}
+
+fn variants(value: &AbstractValue) -> Vec<&AbstractValue> {
+    match value {
+        AbstractValue::Union(variants) => variants.iter().collect(),
+        _ => vec![value],
+    }
+}
+
+// the union of `values`, `None` when there are none
+fn join(values: Vec<&AbstractValue>) -> Option<AbstractValue> {
+    values.into_iter().fold(None, |acc, v| {
+        Some(match acc {
+            Some(acc) => acc.join(v),
+            None => v.clone(),
+        })
+    })
+}
+
+impl fmt::Display for TypeExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeExpr::Number => write!(f, "Number"),
+            TypeExpr::String => write!(f, "String"),
+            TypeExpr::Boolean => write!(f, "Boolean"),
+            TypeExpr::Null => write!(f, "Null"),
+            TypeExpr::Undefined => write!(f, "Undefined"),
+            TypeExpr::Array(element) => match **element {
+                TypeExpr::Union(_) => write!(f, "({})[]", element),
+                _ => write!(f, "{}[]", element),
+            },
+            TypeExpr::Object(fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(name, ty)| format!("{}: {}", name, ty))
+                    .collect();
+                write!(f, "{{ {} }}", fields.join(", "))
+            }
+            TypeExpr::Union(members) => {
+                let members: Vec<String> = members.iter().map(|ty| ty.to_string()).collect();
+                write!(f, "{}", members.join(" | "))
+            }
+            TypeExpr::Named(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod type_expr_tests {
+    use super::*;
+    use crate::parser::parse_type;
+
+    fn admits(ty: &str, value: &AbstractValue) -> bool {
+        parse_type(ty).unwrap().admits(value, &HashMap::new())
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        let ty = parse_type("{ x: number, y: (String | null)[] } | Undefined").unwrap();
+        assert_eq!(
+            ty,
+            TypeExpr::Union(vec![
+                TypeExpr::Object(vec![
+                    ("x".to_string(), TypeExpr::Number),
+                    (
+                        "y".to_string(),
+                        TypeExpr::Array(Box::new(TypeExpr::Union(vec![
+                            TypeExpr::String,
+                            TypeExpr::Null,
+                        ]))),
+                    ),
+                ]),
+                TypeExpr::Undefined,
+            ])
+        );
+        assert_eq!(
+            ty.to_string(),
+            "{ x: Number, y: (String | Null)[] } | Undefined"
+        );
+        assert!(parse_type("Number |").is_err());
+    }
+
+    #[test]
+    fn test_admits_structurally() {
+        let number_or_string =
+            AbstractValue::union_of(vec![AbstractValue::Number, AbstractValue::String]);
+        assert!(admits("Number | String", &number_or_string));
+        assert!(!admits("Number", &number_or_string));
+
+        let numbers = AbstractValue::tuple(vec![AbstractValue::Number, AbstractValue::Number]);
+        assert!(admits("Number[]", &numbers));
+        assert!(!admits("String[]", &numbers));
+
+        let point = AbstractValue::Object(AbstractObject {
+            props: [
+                ("x".to_string(), AbstractValue::Number),
+                ("label".to_string(), AbstractValue::String),
+            ]
+            .into_iter()
+            .collect(),
+        });
+        assert!(admits("{ x: Number }", &point));
+        assert!(!admits("{ x: Number, y: Number }", &point));
+        assert!(admits("Null | { x: Number }", &AbstractValue::Null));
+    }
+
+    #[test]
+    fn test_admits_other_generics() {
+        let mut generics = HashMap::new();
+        generics.insert(
+            "T".to_string(),
+            Box::new(AbstractValue::union_of(vec![
+                AbstractValue::Number,
+                AbstractValue::Null,
+            ])),
+        );
+        let ty = parse_type("T").unwrap();
+        assert!(ty.admits(&AbstractValue::Number, &generics));
+        assert!(!ty.admits(&AbstractValue::String, &generics));
+        generics.insert("V".to_string(), Box::new(AbstractValue::Top));
+        assert!(parse_type("V")
+            .unwrap()
+            .admits(&AbstractValue::String, &generics));
+        // unbound names admit nothing
+        assert!(!parse_type("U")
+            .unwrap()
+            .admits(&AbstractValue::Number, &generics));
+    }
+
+    #[test]
+    fn test_unify_infers_generics() {
+        let generics = vec!["T".to_string(), "U".to_string()];
+        let mut bindings = HashMap::new();
+
+        let numbers = AbstractValue::tuple(vec![AbstractValue::Number]);
+        parse_type("T[]")
+            .unwrap()
+            .unify(&numbers, &generics, &mut bindings)
+            .unwrap();
+        let maybe_string =
+            AbstractValue::union_of(vec![AbstractValue::String, AbstractValue::Null]);
+        parse_type("U | Null")
+            .unwrap()
+            .unify(&maybe_string, &generics, &mut bindings)
+            .unwrap();
+        assert_eq!(bindings["T"], Box::new(AbstractValue::Number));
+        assert_eq!(bindings["U"], Box::new(AbstractValue::String));
+
+        assert_eq!(
+            parse_type("T")
+                .unwrap()
+                .unify(&AbstractValue::String, &generics, &mut bindings),
+            Err(UnifyError::Conflict {
+                generic: "T".to_string(),
+                bound: Box::new(AbstractValue::Number),
+                found: Box::new(AbstractValue::String),
+            })
+        );
+        assert_eq!(
+            parse_type("{ x: T }")
+                .unwrap()
+                .unify(&AbstractValue::Number, &generics, &mut bindings),
+            Err(UnifyError::Mismatch)
+        );
+        // as in `admits`, an unknown name matches nothing
+        let ty = parse_type("{ x: Number, y: Tt[] }").unwrap();
+        assert_eq!(ty.unknown_name(&generics), Some("Tt"));
+        assert_eq!(
+            parse_type("Tt")
+                .unwrap()
+                .unify(&AbstractValue::Number, &generics, &mut bindings),
+            Err(UnifyError::Mismatch)
+        );
+        assert_eq!(parse_type("T | U[]").unwrap().unknown_name(&generics), None);
+    }
+
+    #[test]
+    fn test_instantiate_with_generics() {
+        let mut generics = HashMap::new();
+        generics.insert("T".to_string(), Box::new(AbstractValue::Number));
+
+        let expected = AbstractValue::union_of(vec![
+            AbstractValue::Object(AbstractObject {
+                props: [(
+                    "items".to_string(),
+                    AbstractValue::array_of(Vec::new(), AbstractValue::Number, 0),
+                )]
+                .into_iter()
+                .collect(),
+            }),
+            AbstractValue::Null,
+        ]);
+        let ty = parse_type("{ items: T[] } | Null").unwrap();
+        assert_eq!(ty.instantiate(&generics), Some(expected));
+        assert_eq!(parse_type("U[]").unwrap().instantiate(&generics), None);
+    }
+}
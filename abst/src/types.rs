@@ -1,76 +1,597 @@
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
+    hash::{Hash, Hasher},
+    ops::Deref,
+    rc::Rc,
+};
 
 use crate::{
-    ast::ASTNode,
-    interpret::{merge_values, Merge},
+    ast::{ASTNode, Location},
+    diagnostics::Diagnostic,
+    domain::{AbstractDomain, ValueDomain},
+    interval::Interval,
+    summary::{SummaryTable, DEFAULT_CALL_STRING_LENGTH},
+    type_expr::TypeExpr,
 };
 
+// arrays nested deeper than this are widened to arrays of anything
+const MAX_ARRAY_DEPTH: usize = 2;
+
 /// abstract value
+///
+/// A `Union` is only built by `union_of`, so it is always in canonical form and
+/// two unions of the same values are equal.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum AbstractValue {
+    // no value at all, e.g. the result of code that is never reached;
+    // the identity of `join`
+    Bottom,
+    // any value, e.g. after an error the analysis could not recover from
+    Top,
+    // the runtime `undefined`
     Undefined,
     Null,
+    // any boolean
     Boolean,
+    // `true` or `false`
+    BooleanLiteral(bool),
+    // any number
     Number,
+    // a number within bounds, never `Interval::ANY` (see `AbstractValue::number`);
+    // the literal `42` is `[42, 42]`
+    Interval(Interval),
+    // any string
     String,
+    // the string with this text
+    StringLiteral(String),
     Object(AbstractObject),
-    Array(Vec<AbstractValue>),
-    Union(Vec<AbstractValue>),
+    Array(AbstractArray),
+    Union(Variants),
+    Function(Closure),
+    #[allow(dead_code)]
     Generic(String, Box<AbstractValue>), // String -> T, Box<AbstractValue> -> Concrete Type
 }
 
+// the variants of a `Union`, in the canonical form `union_of` gives them
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Variants(Vec<AbstractValue>);
+
+// An array: the values of its first slots, the value of every slot after those
+// (`Bottom` if there are none) and the length it has at least. The literal
+// `[1, "a"]` has the slots `1` and `"a"` and a length of at least 2; merged
+// with `[2]`, the second slot may be missing, so the length is at least 1.
+//
+// Built by `AbstractValue::array_of`, which keeps it in canonical form.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct AbstractArray {
+    elements: Vec<AbstractValue>,
+    rest: Box<AbstractValue>,
+    min_length: usize,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct AbstractObject {
     pub props: BTreeMap<String, AbstractValue>,
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Function {
-    pub params: Vec<String>,
-    pub generics: Vec<(String, Option<String>)>,
-    pub body: ASTNode,
+    pub params: Vec<(String, Option<TypeExpr>)>,
+    pub generics: Vec<(String, Option<TypeExpr>)>,
+    pub return_type: Option<TypeExpr>,
+    pub body: Box<ASTNode>,
 }
 
+// A function as a value, along with the scopes around its definition.
+//
+// The captured scopes are copies, so assignments the body makes to them are
+// not seen by other closures. The global scope is not captured but looked up
+// when the closure is called.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Closure<V = AbstractValue> {
+    pub function: Function,
+    pub captured: Vec<Scope<V>>,
+}
+
+// the variables and functions declared in one lexical scope
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scope<V = AbstractValue> {
+    pub variables: HashMap<String, V>,
+    pub functions: HashMap<String, Function>,
+}
+
+// Scopes are lexical and function-level: the body of a call gets a fresh scope
+// for its parameters and locals, nested in the scope the function was declared in.
+//
+// `variables` and `functions` belong to the innermost scope; `enclosing` holds
+// the scopes around it, outermost (global) first.
 #[derive(Clone)]
-pub struct AbstractState {
-    pub variables: HashMap<String, AbstractValue>,
+pub struct AbstractState<V = AbstractValue> {
+    pub variables: HashMap<String, V>,
     pub functions: HashMap<String, Function>,
+    pub enclosing: Vec<Scope<V>>,
+    pub diagnostics: Vec<Diagnostic>,
+    // merge of the values of every `return` reached so far
+    pub return_value: Option<V>,
+    // the current path cannot continue: it has definitely executed a `return`,
+    // or a condition ruled it out. The variables of an unreachable state are
+    // ignored, it is the bottom state
+    pub unreachable: bool,
+    // location of the statement being interpreted, attached to diagnostics
+    pub location: Option<Location>,
+    // summaries of the calls being analyzed, shared by every state of the analysis
+    pub summaries: Rc<RefCell<SummaryTable<V>>>,
+    // the loops reached outside of calls, and whether their body may run in any
+    // of the states they were reached in, shared like `summaries`
+    pub loops: Rc<RefCell<Vec<(Location, bool)>>>,
 }
 
 ////////////////////////////////////////////////////////////
 
-impl Merge for AbstractValue {
-    fn merge(&self, other: &Self) -> Self {
+// closures of the same function are equal only with equal captured scopes,
+// so hashing the function alone is consistent with `Eq`
+impl<V> Hash for Closure<V> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.function.hash(state);
+    }
+}
+
+impl<V> Closure<V> {
+    // whether both are closures of the same function over the same scopes,
+    // which may differ in the values of their variables
+    fn same_scopes(&self, other: &Closure<V>) -> bool {
+        self.function == other.function
+            && self.captured.len() == other.captured.len()
+            && self
+                .captured
+                .iter()
+                .zip(&other.captured)
+                .all(|(a, b)| a.functions == b.functions)
+    }
+}
+
+impl<V> fmt::Debug for Closure<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let params: Vec<&str> = self
+            .function
+            .params
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        write!(f, "fn({})", params.join(", "))
+    }
+}
+
+// not derived, which would require `V: Default`
+impl<V> Default for Scope<V> {
+    fn default() -> Self {
+        Scope {
+            variables: HashMap::new(),
+            functions: HashMap::new(),
+        }
+    }
+}
+
+impl Deref for Variants {
+    type Target = [AbstractValue];
+
+    fn deref(&self) -> &[AbstractValue] {
+        &self.0
+    }
+}
+
+impl IntoIterator for Variants {
+    type Item = AbstractValue;
+    type IntoIter = std::vec::IntoIter<AbstractValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl fmt::Debug for Variants {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl AbstractArray {
+    pub fn elements(&self) -> &[AbstractValue] {
+        &self.elements
+    }
+
+    pub fn rest(&self) -> &AbstractValue {
+        &self.rest
+    }
+
+    pub fn min_length(&self) -> usize {
+        self.min_length
+    }
+
+    // the value of slot `i`, in an array long enough to have it
+    pub fn slot(&self, i: usize) -> &AbstractValue {
+        self.elements.get(i).unwrap_or(&self.rest)
+    }
+
+    // `f` of every slot of `self` and the same slot of `other`, in an array of
+    // at least `min_length`
+    fn zip_with(
+        &self,
+        other: &AbstractArray,
+        min_length: usize,
+        f: impl Fn(&AbstractValue, &AbstractValue) -> AbstractValue,
+    ) -> AbstractValue {
+        let listed = usize::max(self.elements.len(), other.elements.len());
+        AbstractValue::array_of(
+            (0..listed)
+                .map(|i| f(self.slot(i), other.slot(i)))
+                .collect(),
+            f(&self.rest, &other.rest),
+            min_length,
+        )
+    }
+
+    // `f` of every slot
+    fn map(&self, f: impl Fn(&AbstractValue) -> AbstractValue) -> AbstractValue {
+        AbstractValue::array_of(
+            self.elements.iter().map(&f).collect(),
+            f(&self.rest),
+            self.min_length,
+        )
+    }
+}
+
+// `[1, 2?, ...Number]`: slots past the length the array has at least are
+// marked `?`, and `...` gives the value of every slot after the others
+impl fmt::Debug for AbstractArray {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut slots: Vec<String> = self
+            .elements
+            .iter()
+            .enumerate()
+            .map(|(i, e)| {
+                if i < self.min_length {
+                    format!("{:?}", e)
+                } else {
+                    format!("{:?}?", e)
+                }
+            })
+            .collect();
+        if *self.rest != AbstractValue::Bottom {
+            slots.push(format!("...{:?}", self.rest));
+        }
+        write!(f, "[{}]", slots.join(", "))
+    }
+}
+
+impl AbstractDomain for AbstractValue {
+    fn bottom() -> Self {
+        AbstractValue::Bottom
+    }
+
+    fn top() -> Self {
+        AbstractValue::Top
+    }
+
+    fn join(&self, other: &Self) -> Self {
+        AbstractValue::union_of([self.clone(), other.clone()])
+    }
+
+    // The values described by both `self` and `other`, the greatest lower bound
+    // of `leq`. It distributes over unions, so only values of the same kind
+    // have anything in common.
+    fn meet(&self, other: &AbstractValue) -> AbstractValue {
         use AbstractValue::*;
 
-        // step1: identical => return self
-        if self == other {
-            return self.clone();
+        if let Union(variants) = self {
+            return AbstractValue::union_of(variants.iter().map(|v| v.meet(other)));
+        }
+        if let Union(_) = other {
+            return other.meet(self);
+        }
+        match (self, other) {
+            _ if self.leq(other) => self.clone(),
+            _ if other.leq(self) => other.clone(),
+            // below both, an array is at least as long as either
+            (Array(a), Array(b)) => a.zip_with(
+                b,
+                usize::max(a.min_length, b.min_length),
+                AbstractValue::meet,
+            ),
+            // below both, a property missing on one side must be allowed to be
+            // `undefined` on the other
+            (Object(a), Object(b)) => {
+                let only_in = |x: &AbstractObject, y: &AbstractObject| {
+                    x.props
+                        .iter()
+                        .filter(|(key, _)| !y.props.contains_key(*key))
+                        .all(|(_, v)| Undefined.leq(v))
+                };
+                if !only_in(a, b) || !only_in(b, a) {
+                    return Bottom;
+                }
+                Object(AbstractObject {
+                    props: a
+                        .props
+                        .iter()
+                        .filter_map(|(key, v)| Some((key.clone(), v.meet(b.props.get(key)?))))
+                        .collect(),
+                })
+            }
+            (Function(a), Function(b)) if a.same_scopes(b) => {
+                let mut captured = a.captured.clone();
+                for (scope, other_scope) in captured.iter_mut().zip(&b.captured) {
+                    scope.variables = scope
+                        .variables
+                        .iter()
+                        .filter_map(|(name, v)| {
+                            Some((name.clone(), v.meet(other_scope.variables.get(name)?)))
+                        })
+                        .collect();
+                }
+                Function(Closure {
+                    function: a.function.clone(),
+                    captured,
+                })
+            }
+            _ => match (self.as_interval(), other.as_interval()) {
+                (Some(a), Some(b)) => match a.meet(&b) {
+                    Some(interval) => AbstractValue::number(Some(interval)),
+                    None => Bottom,
+                },
+                _ => Bottom,
+            },
         }
+    }
+
+    // Whether `self` describes no more values than `other`, the partial order
+    // in which `join` is the least upper bound: `a.leq(b)` iff `a.join(b) == b`.
+    fn leq(&self, other: &AbstractValue) -> bool {
+        use AbstractValue::*;
 
-        // step2: self is undefined => return other
-        if matches!(self, Undefined) {
-            return other.clone();
+        if let Union(variants) = self {
+            return variants.iter().all(|v| v.leq(other));
+        }
+        if let Union(variants) = other {
+            // a canonical union has at most one variant of each kind to compare with
+            return variants.iter().any(|v| self.leq(v));
+        }
+        match (self, other) {
+            (Bottom, _) | (_, Top) => true,
+            (_, Bottom) | (Top, _) => false,
+            (Array(a), Array(b)) => {
+                let listed = usize::max(a.elements.len(), b.elements.len());
+                a.min_length >= b.min_length
+                    && (0..listed).all(|i| a.slot(i).leq(b.slot(i)))
+                    && a.rest.leq(&b.rest)
+            }
+            // a missing property is merged as `undefined`
+            (Object(a), Object(b)) => {
+                a.props
+                    .iter()
+                    .all(|(key, v)| b.props.get(key).is_some_and(|w| v.leq(w)))
+                    && b.props
+                        .iter()
+                        .all(|(key, w)| a.props.contains_key(key) || Undefined.leq(w))
+            }
+            (Function(a), Function(b)) if a.same_scopes(b) => {
+                a.captured.iter().zip(&b.captured).all(|(x, y)| {
+                    x.variables
+                        .iter()
+                        .all(|(name, v)| y.variables.get(name).is_some_and(|w| v.leq(w)))
+                })
+            }
+            (StringLiteral(_), String) | (BooleanLiteral(_), Boolean) => true,
+            _ => match (self.as_interval(), other.as_interval()) {
+                (Some(a), Some(b)) => b.contains(&a),
+                _ => self == other,
+            },
         }
-        if matches!(other, Undefined) {
+    }
+
+    // Widening operator used to make loop analysis terminate.
+    //
+    // `self` is the value from the previous iteration and `next` the value after
+    // running the loop body once more. The result over-approximates both, and
+    // arrays nested deeper than `MAX_ARRAY_DEPTH` are collapsed into an array
+    // of any length and any values, so a chain like `x = [x]` cannot grow
+    // forever. The bounds of a number that keep moving go
+    // to infinity (see `Interval::widen`), so `i = i + 1` cannot either, and
+    // new literals become their type, so neither can `s = s + "a"`.
+    fn widen(&self, next: &AbstractValue) -> AbstractValue {
+        self.join(next).extrapolate(self).limit_depth(0)
+    }
+
+    // Narrowing operator, recovering some of the precision widening gave up.
+    //
+    // `self` is the widened value and `next` the value after running the loop
+    // body once more from it. The infinite bounds of numbers take those of
+    // `next` (see `Interval::narrow`); other values become `next` when it is
+    // more precise.
+    fn narrow(&self, next: &AbstractValue) -> AbstractValue {
+        use AbstractValue::*;
+
+        // narrowing never goes above `self`, whatever `next` is
+        if !next.leq(self) {
             return self.clone();
         }
+        if let Union(variants) = next {
+            return AbstractValue::union_of(variants.iter().map(|v| self.narrow(v)));
+        }
+        match (self.same_kind(next), next) {
+            (Some(Array(a)), Array(b)) => a.zip_with(b, b.min_length, AbstractValue::narrow),
+            (Some(Object(a)), Object(b)) if a.props.keys().eq(b.props.keys()) => {
+                Object(AbstractObject {
+                    props: a
+                        .props
+                        .iter()
+                        .zip(b.props.values())
+                        .map(|((k, x), y)| (k.clone(), x.narrow(y)))
+                        .collect(),
+                })
+            }
+            (Some(a), b) => match (a.as_interval(), b.as_interval()) {
+                (Some(a), Some(b)) => AbstractValue::number(Some(a.narrow(&b))),
+                _ => next.clone(),
+            },
+            _ => next.clone(),
+        }
+    }
+}
 
-        // Step 3: type-specific merging
-        match (self, other) {
-            // Array type
-            (Array(a_elements), Array(b_elements)) => {
-                let max_length = usize::max(a_elements.len(), b_elements.len());
-                let merged_elements = (0..max_length)
-                    .map(|i| {
-                        let a_elem = a_elements.get(i).unwrap_or(&Undefined);
-                        let b_elem = b_elements.get(i).unwrap_or(&Undefined);
-                        a_elem.merge(b_elem)
-                    })
-                    .collect();
-                Array(merged_elements)
+impl AbstractValue {
+    // a number within `interval`, plain `Number` when it is unbounded
+    pub fn number(interval: Option<Interval>) -> AbstractValue {
+        match interval {
+            Some(interval) if !interval.is_any() => AbstractValue::Interval(interval),
+            _ => AbstractValue::Number,
+        }
+    }
+
+    // the bounds of a number, `None` for other values
+    pub fn as_interval(&self) -> Option<Interval> {
+        match self {
+            AbstractValue::Number => Some(Interval::ANY),
+            AbstractValue::Interval(interval) => Some(*interval),
+            _ => None,
+        }
+    }
+
+    // The type of `self`, without the bounds of its numbers and the values of
+    // its literals.
+    //
+    // Generics are bound to types, so `add(1, 2)` binds `T` to `Number` rather
+    // than to the bounds of `1` and `2`.
+    pub fn base_type(&self) -> AbstractValue {
+        match self {
+            AbstractValue::Interval(_) => AbstractValue::Number,
+            AbstractValue::BooleanLiteral(_) => AbstractValue::Boolean,
+            AbstractValue::StringLiteral(_) => AbstractValue::String,
+            AbstractValue::Array(array) => array.map(AbstractValue::base_type),
+            AbstractValue::Object(obj) => AbstractValue::Object(AbstractObject {
+                props: obj
+                    .props
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.base_type()))
+                    .collect(),
+            }),
+            AbstractValue::Union(variants) => {
+                AbstractValue::union_of(variants.iter().map(|v| v.base_type()))
             }
-            // Object type
+            AbstractValue::Generic(name, concrete) => {
+                AbstractValue::Generic(name.clone(), Box::new(concrete.base_type()))
+            }
+            _ => self.clone(),
+        }
+    }
+
+    // The array with the slots `elements`, then `rest`, and a length of at
+    // least `min_length`, in canonical form: `Bottom` if a slot it has has no
+    // value, and only listing the slots that may be missing up to the last one
+    // that differs from `rest`.
+    pub fn array_of(
+        mut elements: Vec<AbstractValue>,
+        mut rest: AbstractValue,
+        min_length: usize,
+    ) -> AbstractValue {
+        while elements.len() < min_length {
+            elements.push(rest.clone());
+        }
+        if elements[..min_length].contains(&AbstractValue::Bottom) {
+            return AbstractValue::Bottom;
+        }
+        // no array is long enough to have a slot without a value
+        if let Some(end) = elements.iter().position(|e| *e == AbstractValue::Bottom) {
+            elements.truncate(end);
+            rest = AbstractValue::Bottom;
+        }
+        while elements.len() > min_length && elements.last() == Some(&rest) {
+            elements.pop();
+        }
+        AbstractValue::Array(AbstractArray {
+            elements,
+            rest: Box::new(rest),
+            min_length,
+        })
+    }
+
+    // the array of exactly `elements`, e.g. a literal
+    pub fn tuple(elements: Vec<AbstractValue>) -> AbstractValue {
+        let length = elements.len();
+        AbstractValue::array_of(elements, AbstractValue::Bottom, length)
+    }
+
+    // The canonical union of `values`.
+    //
+    // Nested unions are flattened, `Bottom` is dropped and `Top` absorbs the
+    // rest. Variants that describe the same kind of value are combined into one:
+    // all arrays into a single array, all objects into a single object and the
+    // closures of one function into a single closure and all numbers into the
+    // interval covering them, so `[Number] | [String]` becomes `[Number | String]`.
+    // Literals are kept apart (`"a" | "b"`), unless their type is there too, or
+    // they are `true` and `false`. What remains is deduplicated and sorted, so
+    // any two unions of the same values are represented the same way.
+    pub fn union_of(values: impl IntoIterator<Item = AbstractValue>) -> AbstractValue {
+        let mut flat = Vec::new();
+        for value in values {
+            value.flatten_into(&mut flat);
+        }
+
+        let mut variants: Vec<AbstractValue> = Vec::new();
+        for value in flat {
+            match value {
+                AbstractValue::Bottom => continue,
+                AbstractValue::Top => return AbstractValue::Top,
+                _ => {}
+            }
+            // what `value` combines into may combine with further variants
+            // (`"a" | "b"` with `String`), so keep going until none is left
+            let mut value = value;
+            while let Some((i, combined)) = variants
+                .iter()
+                .enumerate()
+                .find_map(|(i, variant)| Some((i, variant.combine(&value)?)))
+            {
+                variants.swap_remove(i);
+                value = combined;
+            }
+            variants.push(value);
+        }
+
+        variants.sort_by_cached_key(AbstractValue::sort_key);
+        match variants.len() {
+            0 => AbstractValue::Bottom,
+            1 => variants.pop().unwrap(),
+            _ => AbstractValue::Union(Variants(variants)),
+        }
+    }
+
+    fn flatten_into(self, flat: &mut Vec<AbstractValue>) {
+        match self {
+            AbstractValue::Union(variants) => {
+                for v in variants {
+                    v.flatten_into(flat);
+                }
+            }
+            _ => flat.push(self),
+        }
+    }
+
+    // the single variant describing both `self` and `other`, which are not unions,
+    // if they are the same kind of value
+    fn combine(&self, other: &AbstractValue) -> Option<AbstractValue> {
+        use AbstractValue::*;
+
+        Some(match (self, other) {
+            // Array type, as long as the shorter one at least
+            (Array(a), Array(b)) => a.zip_with(
+                b,
+                usize::min(a.min_length, b.min_length),
+                AbstractValue::join,
+            ),
+            // Object type, a property missing on one side reads as `undefined` there
             (Object(a_obj), Object(b_obj)) => {
                 let keys: HashSet<_> = a_obj.props.keys().chain(b_obj.props.keys()).collect();
                 let merged_props = keys
@@ -78,71 +599,680 @@ impl Merge for AbstractValue {
                     .map(|key| {
                         let a_val = a_obj.props.get(key).unwrap_or(&Undefined);
                         let b_val = b_obj.props.get(key).unwrap_or(&Undefined);
-                        (key.clone(), a_val.merge(b_val))
+                        (key.clone(), a_val.join(b_val))
                     })
                     .collect();
                 Object(AbstractObject {
                     props: merged_props,
                 })
             }
-            // other cases => merge into Union
-            _ => {
-                let mut variants = HashSet::new();
-                self.collect_variants(&mut variants);
-                other.collect_variants(&mut variants);
-                if variants.len() == 1 {
-                    variants.into_iter().next().unwrap()
-                } else {
-                    Union(variants.into_iter().collect())
+            // the same function over the same scopes => merge what the closures captured
+            (Function(a), Function(b)) if a.same_scopes(b) => {
+                let mut captured = a.captured.clone();
+                for (scope, other_scope) in captured.iter_mut().zip(&b.captured) {
+                    join_variables(
+                        &mut scope.variables,
+                        &other_scope.variables,
+                        AbstractValue::join,
+                    );
                 }
+                Function(Closure {
+                    function: a.function.clone(),
+                    captured,
+                })
+            }
+            (String, StringLiteral(_)) | (StringLiteral(_), String) => String,
+            (Boolean, BooleanLiteral(_)) | (BooleanLiteral(_), Boolean) => Boolean,
+            (BooleanLiteral(a), BooleanLiteral(b)) if a != b => Boolean,
+            // numbers => the interval covering both
+            _ if self.as_interval().is_some() && other.as_interval().is_some() => {
+                let (a, b) = (self.as_interval()?, other.as_interval()?);
+                AbstractValue::number(Some(a.hull(&b)))
             }
+            _ if self == other => self.clone(),
+            _ => return None,
+        })
+    }
+
+    // the variant of `self` of the same kind as `value` (see `rank`), which a
+    // canonical union has at most one of, unless they are closures or strings
+    fn same_kind(&self, value: &AbstractValue) -> Option<AbstractValue> {
+        match self {
+            AbstractValue::Union(variants) => {
+                variants.iter().find(|v| v.rank() == value.rank()).cloned()
+            }
+            _ if self.rank() == value.rank() => Some(self.clone()),
+            _ => None,
         }
     }
-}
 
-impl AbstractValue {
-    fn collect_variants(&self, set: &mut HashSet<AbstractValue>) {
+    // variants are ordered by kind, then (for the kinds that may appear more
+    // than once in a canonical union) by their description
+    fn sort_key(&self) -> (u8, String) {
+        (self.rank(), self.description())
+    }
+
+    // the kind of value, the same for a type and its literals
+    pub fn rank(&self) -> u8 {
+        match self {
+            AbstractValue::Bottom => 0,
+            AbstractValue::Undefined => 1,
+            AbstractValue::Null => 2,
+            AbstractValue::Boolean | AbstractValue::BooleanLiteral(_) => 3,
+            AbstractValue::Number | AbstractValue::Interval(_) => 4,
+            AbstractValue::String | AbstractValue::StringLiteral(_) => 5,
+            AbstractValue::Array(_) => 6,
+            AbstractValue::Object(_) => 7,
+            AbstractValue::Function(_) => 8,
+            AbstractValue::Generic(_, _) => 9,
+            AbstractValue::Union(_) => 10,
+            AbstractValue::Top => 11,
+        }
+    }
+
+    fn description(&self) -> String {
         match self {
-            AbstractValue::Union(values) => {
-                for v in values {
-                    v.collect_variants(set);
+            AbstractValue::Function(closure) => {
+                let functions: Vec<BTreeMap<&String, &Function>> = closure
+                    .captured
+                    .iter()
+                    .map(|scope| scope.functions.iter().collect())
+                    .collect();
+                format!("{:?} {:?}", closure.function, functions)
+            }
+            AbstractValue::Generic(name, concrete) => format!("{} {:?}", name, concrete),
+            AbstractValue::StringLiteral(text) => text.clone(),
+            _ => String::new(),
+        }
+    }
+
+    // `self`, the join of `previous` and the next value, with the bounds of its
+    // numbers widened from those of `previous` and the literals not in
+    // `previous` replaced by their type
+    fn extrapolate(&self, previous: &AbstractValue) -> AbstractValue {
+        use AbstractValue::*;
+
+        match (self, previous.same_kind(self)) {
+            (StringLiteral(_) | BooleanLiteral(_), _) if !self.leq(previous) => self.base_type(),
+            (Union(variants), _) => {
+                AbstractValue::union_of(variants.iter().map(|v| v.extrapolate(previous)))
+            }
+            (Interval(next), Some(Interval(previous))) => Interval(previous.widen(next)),
+            (Array(array), Some(Array(previous))) => {
+                array.zip_with(&previous, array.min_length, AbstractValue::extrapolate)
+            }
+            (Object(obj), Some(Object(previous))) => Object(AbstractObject {
+                props: obj
+                    .props
+                    .iter()
+                    .map(|(k, v)| match previous.props.get(k) {
+                        Some(p) => (k.clone(), v.extrapolate(p)),
+                        None => (k.clone(), v.clone()),
+                    })
+                    .collect(),
+            }),
+            _ => self.clone(),
+        }
+    }
+
+    fn limit_depth(&self, depth: usize) -> AbstractValue {
+        match self {
+            // above every array, however deeply nested
+            AbstractValue::Array(_) if depth >= MAX_ARRAY_DEPTH => {
+                AbstractValue::array_of(Vec::new(), AbstractValue::Top, 0)
+            }
+            AbstractValue::Array(array) => array.map(|e| e.limit_depth(depth + 1)),
+            AbstractValue::Union(variants) => {
+                variants.iter().fold(AbstractValue::Bottom, |acc, v| {
+                    acc.join(&v.limit_depth(depth))
+                })
+            }
+            AbstractValue::Object(obj) => AbstractValue::Object(AbstractObject {
+                props: obj
+                    .props
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.limit_depth(depth)))
+                    .collect(),
+            }),
+            _ => self.clone(),
+        }
+    }
+
+    // `self` or the runtime `undefined`, e.g. for a function that may fall off its end
+    pub fn or_undefined(&self) -> AbstractValue {
+        self.join(&AbstractValue::Undefined)
+    }
+
+    // `Some(b)` when every runtime value described by `self` converts to `b` in a
+    // boolean context, `None` when it may be either
+    pub fn truthiness(&self) -> Option<bool> {
+        match self {
+            AbstractValue::Bottom | AbstractValue::Top => None,
+            AbstractValue::Undefined | AbstractValue::Null => Some(false),
+            AbstractValue::Object(_) | AbstractValue::Array(_) | AbstractValue::Function(_) => {
+                Some(true)
+            }
+            AbstractValue::Boolean | AbstractValue::Number | AbstractValue::String => None,
+            AbstractValue::BooleanLiteral(b) => Some(*b),
+            AbstractValue::StringLiteral(text) => Some(!text.is_empty()),
+            // `NaN` is not in an interval
+            AbstractValue::Interval(interval) => {
+                Interval::constant(0.0).and_then(|zero| interval.compare("!=", &zero))
+            }
+            AbstractValue::Generic(_, concrete) => concrete.truthiness(),
+            AbstractValue::Union(variants) => {
+                let mut values = variants.iter().map(|v| v.truthiness());
+                let first = values.next().flatten();
+                if values.all(|v| v == first) {
+                    first
+                } else {
+                    None
                 }
             }
-            _ => {
-                set.insert(self.clone());
+        }
+    }
+
+    // the values of `self` that may be falsy (`None` if there are none)
+    pub fn falsy_part(&self) -> Option<AbstractValue> {
+        self.filter_variants(&|v| v.truthiness() != Some(true))
+    }
+
+    // the values of `self` that may be truthy (`None` if there are none)
+    pub fn truthy_part(&self) -> Option<AbstractValue> {
+        self.filter_variants(&|v| v.truthiness() != Some(false))
+    }
+
+    // keep the variants of a union (or the value itself) satisfying `keep`
+    fn filter_variants(&self, keep: &dyn Fn(&AbstractValue) -> bool) -> Option<AbstractValue> {
+        match self {
+            AbstractValue::Union(variants) => {
+                variants
+                    .iter()
+                    .filter(|v| keep(v))
+                    .fold(None, |acc: Option<AbstractValue>, v| {
+                        Some(match acc {
+                            Some(acc) => acc.join(v),
+                            None => v.clone(),
+                        })
+                    })
             }
+            _ if keep(self) => Some(self.clone()),
+            _ => None,
         }
     }
 }
 
-impl AbstractState {
+impl<V: ValueDomain> AbstractState<V> {
     pub fn new() -> Self {
+        AbstractState::with_call_string_length(DEFAULT_CALL_STRING_LENGTH)
+    }
+
+    // a state whose calls are distinguished by their last `k` call sites
+    pub fn with_call_string_length(k: usize) -> Self {
         AbstractState {
             variables: HashMap::new(),
             functions: HashMap::new(),
+            enclosing: Vec::new(),
+            diagnostics: Vec::new(),
+            return_value: None,
+            unreachable: false,
+            location: None,
+            summaries: Rc::new(RefCell::new(SummaryTable::new(k))),
+            loops: Rc::default(),
+        }
+    }
+
+    // assign to the innermost visible variable named `name`,
+    // declaring it in the innermost scope if there is none
+    pub fn assign(&mut self, name: &str, value: V) {
+        if !self.variables.contains_key(name) {
+            self.record_read(name);
+            if let Some(scope) = self
+                .enclosing
+                .iter_mut()
+                .rev()
+                .find(|scope| scope.variables.contains_key(name))
+            {
+                scope.variables.insert(name.to_string(), value);
+                return;
+            }
         }
+        self.variables.insert(name.to_string(), value);
     }
 
-    pub fn assign(&mut self, name: &str, value: AbstractValue) {
+    // declare `name` in the innermost scope, shadowing any outer variable
+    pub fn declare(&mut self, name: &str, value: V) {
         self.variables.insert(name.to_string(), value);
     }
 
-    pub fn get(&self, name: &str) -> Option<&AbstractValue> {
-        self.variables.get(name)
+    pub fn get(&self, name: &str) -> Option<&V> {
+        self.variables.get(name).or_else(|| {
+            self.enclosing
+                .iter()
+                .rev()
+                .find_map(|scope| scope.variables.get(name))
+        })
     }
 
-    // e.g. for control flow
-    pub fn merge(&mut self, other: &AbstractState) {
-        for (key, value) in &other.variables {
-            if let Some(existing_value) = self.variables.get(key) {
-                let merged_value = merge_values(existing_value, value);
-                self.variables.insert(key.clone(), merged_value);
-            } else {
-                self.variables.insert(key.clone(), value.clone());
+    // `name` is about to be looked up: when it is not declared in the innermost
+    // scope, the calls being analyzed depend on what it resolves to outside
+    pub fn record_read(&mut self, name: &str) {
+        if !self.variables.contains_key(name) && !self.functions.contains_key(name) {
+            self.summaries.borrow_mut().record_read(name);
+        }
+    }
+
+    // the innermost function named `name`, along with the depth of the scope
+    // declaring it (0 for the global scope)
+    pub fn lookup_function(&self, name: &str) -> Option<(usize, &Function)> {
+        if let Some(function) = self.functions.get(name) {
+            return Some((self.enclosing.len(), function));
+        }
+        self.enclosing
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(depth, scope)| scope.functions.get(name).map(|f| (depth, f)))
+    }
+
+    // every visible scope, outermost first, up to the one at `depth`
+    fn scopes(&self, depth: usize) -> Vec<Scope<V>> {
+        let mut scopes = self.enclosing.clone();
+        scopes.push(Scope {
+            variables: self.variables.clone(),
+            functions: self.functions.clone(),
+        });
+        scopes.truncate(depth + 1);
+        scopes
+    }
+
+    // state for the body of a function declared at `depth`: an empty scope
+    // nested in the scopes visible at the declaration
+    pub fn enter_function(&self, depth: usize) -> AbstractState<V> {
+        self.nested(self.scopes(depth))
+    }
+
+    // the non-global scopes up to `depth` for a closure to capture
+    pub fn capture(&self, depth: usize) -> Vec<Scope<V>> {
+        self.scopes(depth).split_off(1)
+    }
+
+    // state for the body of a call to `closure`: an empty scope nested in
+    // the captured scopes and the current global scope
+    pub fn enter_closure(&self, closure: &Closure<V>) -> AbstractState<V> {
+        let mut enclosing = self.scopes(0);
+        enclosing.extend(closure.captured.iter().cloned());
+        self.nested(enclosing)
+    }
+
+    // an empty scope nested in `enclosing`, sharing the summaries of `self`
+    fn nested(&self, enclosing: Vec<Scope<V>>) -> AbstractState<V> {
+        AbstractState {
+            variables: HashMap::new(),
+            functions: HashMap::new(),
+            enclosing,
+            diagnostics: Vec::new(),
+            return_value: None,
+            unreachable: false,
+            location: None,
+            summaries: self.summaries.clone(),
+            loops: self.loops.clone(),
+        }
+    }
+
+    // write back what the body of a call (see `enter_function` and `enter_closure`)
+    // assigned to the scopes around it, given as `(depth, name, value)`, for the
+    // first `live` scopes which are shared with `self`
+    pub fn leave_function(&mut self, effects: &[(usize, String, V)], live: usize) {
+        for (depth, name, value) in effects {
+            if *depth >= live {
+                continue;
             }
+            let variables = match self.enclosing.get_mut(*depth) {
+                Some(scope) => &mut scope.variables,
+                None => &mut self.variables,
+            };
+            variables.insert(name.clone(), value.clone());
+        }
+    }
+
+    // e.g. for control flow
+    pub fn merge(&mut self, other: &AbstractState<V>) {
+        self.join(other, V::join);
+    }
+
+    // record a diagnostic at the current location,
+    // ignoring duplicates (e.g. from re-analyzing a loop body)
+    pub fn report(&mut self, mut diagnostic: Diagnostic) {
+        if diagnostic.location.is_none() {
+            diagnostic.location = self.location;
+        }
+        if !self.diagnostics.contains(&diagnostic) {
+            self.diagnostics.push(diagnostic);
+        }
+    }
+
+    // Record whether the body of the loop at the current location may run.
+    //
+    // Reached from a state that is not stable yet (in an enclosing loop), the
+    // body may not run only for now, and in a call only for its arguments, so
+    // only loops outside of calls are recorded, and a loop counts as entered
+    // when it is in any of the states it is reached in.
+    pub fn record_loop(&self, entered: bool) {
+        let Some(location) = self.location else {
+            return;
+        };
+        if self.summaries.borrow().depth() > 0 {
+            return;
+        }
+        let mut loops = self.loops.borrow_mut();
+        match loops.iter_mut().find(|(other, _)| *other == location) {
+            Some((_, entered_before)) => *entered_before |= entered,
+            None => loops.push((location, entered)),
+        }
+    }
+
+    // widen every variable of `self` (previous loop iteration) with `next`
+    pub fn widen(&mut self, next: &AbstractState<V>) {
+        self.join(next, V::widen);
+    }
+
+    // narrow every variable of `self` (widened loop state) with `next`
+    pub fn narrow(&mut self, next: &AbstractState<V>) {
+        self.join(next, V::narrow);
+    }
+
+    // Combine the state of another control flow path into this one.
+    //
+    // An unreachable path (one that has returned, say) does not reach the join
+    // point, so only its return value is kept. If `self` is unreachable, the
+    // result is just `other`.
+    // Assignments to enclosing scopes outlive the call either way, so those are
+    // always combined.
+    fn join(&mut self, other: &AbstractState<V>, combine: fn(&V, &V) -> V) {
+        self.return_value = match (&self.return_value, &other.return_value) {
+            (Some(a), Some(b)) => Some(combine(a, b)),
+            (a, b) => a.clone().or_else(|| b.clone()),
+        };
+        for diagnostic in &other.diagnostics {
+            self.report(diagnostic.clone());
         }
         for (key, function) in &other.functions {
             self.functions.insert(key.clone(), function.clone());
         }
+        for (scope, other_scope) in self.enclosing.iter_mut().zip(&other.enclosing) {
+            join_variables(&mut scope.variables, &other_scope.variables, combine);
+        }
+
+        if other.unreachable {
+            return;
+        }
+        if self.unreachable {
+            self.variables = other.variables.clone();
+            self.unreachable = false;
+            return;
+        }
+
+        join_variables(&mut self.variables, &other.variables, combine);
+    }
+
+    // record a `return` of `value` on the current path
+    pub fn record_return(&mut self, value: V) {
+        self.return_value = Some(match &self.return_value {
+            Some(existing) => existing.join(&value),
+            None => value,
+        });
+        self.unreachable = true;
+    }
+
+    // whether both states bind the same variables and returns to the same values
+    pub fn same_variables(&self, other: &AbstractState<V>) -> bool {
+        self.return_value == other.return_value
+            && self.variables == other.variables
+            && self
+                .enclosing
+                .iter()
+                .zip(&other.enclosing)
+                .all(|(a, b)| a.variables == b.variables)
+    }
+}
+
+fn join_variables<V: Clone>(
+    into: &mut HashMap<String, V>,
+    other: &HashMap<String, V>,
+    combine: fn(&V, &V) -> V,
+) {
+    for (key, value) in other {
+        let joined = match into.get(key) {
+            Some(existing_value) => combine(existing_value, value),
+            None => value.clone(),
+        };
+        into.insert(key.clone(), joined);
+    }
+}
+
+#[cfg(test)]
+mod types_tests {
+    use super::*;
+
+    // a small deterministic generator (xorshift), so failures are reproducible
+    struct Rng(u64);
+
+    impl Rng {
+        fn below(&mut self, n: u64) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 % n
+        }
+
+        // `value` itself, a value above or below it, or an arbitrary one
+        fn related(&mut self, value: &AbstractValue) -> AbstractValue {
+            match self.below(4) {
+                0 => value.clone(),
+                1 => value.join(&self.value(3)),
+                2 => value.meet(&self.value(3)),
+                _ => self.value(3),
+            }
+        }
+
+        fn values(&mut self, count: u64, depth: usize) -> Vec<AbstractValue> {
+            (0..count).map(|_| self.value(depth)).collect()
+        }
+
+        // an arbitrary value
+        fn value(&mut self, depth: usize) -> AbstractValue {
+            let kinds = if depth == 0 { 7 } else { 11 };
+            match self.below(kinds) {
+                0 => AbstractValue::Bottom,
+                1 => AbstractValue::Undefined,
+                2 => AbstractValue::Null,
+                3 if self.below(2) == 0 => AbstractValue::BooleanLiteral(self.below(2) == 0),
+                3 => AbstractValue::Boolean,
+                4 => AbstractValue::Number,
+                5 if self.below(2) == 0 => {
+                    AbstractValue::StringLiteral(["", "a", "b"][self.below(3) as usize].to_string())
+                }
+                5 => AbstractValue::String,
+                6 if self.below(4) == 0 => AbstractValue::Top,
+                6 => {
+                    let bounds = [f64::NEG_INFINITY, -1.0, 0.0, 0.5, 2.0, f64::INFINITY];
+                    let a = bounds[self.below(6) as usize];
+                    let b = bounds[self.below(6) as usize];
+                    let interval = Interval {
+                        lo: a.min(b),
+                        hi: a.max(b),
+                        integral: a != 0.5 && b != 0.5 && self.below(2) == 0,
+                    };
+                    AbstractValue::number(Some(interval))
+                }
+                7 => {
+                    let listed = self.below(3);
+                    let elements = self.values(listed, depth - 1);
+                    let rest = match self.below(2) {
+                        0 => AbstractValue::Bottom,
+                        _ => self.value(depth - 1),
+                    };
+                    let min_length = self.below(listed + 2) as usize;
+                    AbstractValue::array_of(elements, rest, min_length)
+                }
+                8 => {
+                    let mut props = BTreeMap::new();
+                    for key in ["a", "b", "c"] {
+                        if self.below(2) == 0 {
+                            props.insert(key.to_string(), self.value(depth - 1));
+                        }
+                    }
+                    AbstractValue::Object(AbstractObject { props })
+                }
+                9 => {
+                    let count = 2 + self.below(2);
+                    AbstractValue::union_of(self.values(count, depth - 1))
+                }
+                _ => {
+                    let name = ["f", "g"][self.below(2) as usize];
+                    let mut scope = Scope::default();
+                    if self.below(2) == 0 {
+                        scope
+                            .variables
+                            .insert("v".to_string(), self.value(depth - 1));
+                    }
+                    if self.below(2) == 0 {
+                        scope.functions.insert("h".to_string(), function("h"));
+                    }
+                    AbstractValue::Function(Closure {
+                        function: function(name),
+                        captured: vec![scope],
+                    })
+                }
+            }
+        }
+    }
+
+    // a function returning `name`
+    fn function(name: &str) -> Function {
+        Function {
+            params: vec![],
+            generics: vec![],
+            return_type: None,
+            body: Box::new(ASTNode::Variable(name.to_string())),
+        }
+    }
+
+    // Run `law` on `SAMPLES` triples of values. Independent values are rarely
+    // ordered, so `b` and `c` are mostly derived from the others, making
+    // premises like `a.leq(b) && b.leq(c)` hold often enough to be tested.
+    fn check(law: impl Fn(&AbstractValue, &AbstractValue, &AbstractValue) -> bool) {
+        const SAMPLES: usize = 2000;
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        for _ in 0..SAMPLES {
+            let a = rng.value(3);
+            let b = rng.related(&a);
+            let c = match rng.below(4) {
+                0 => rng.related(&a),
+                1 => rng.related(&b),
+                2 => rng.related(&a.join(&b)),
+                _ => rng.related(&a.meet(&b)),
+            };
+            assert!(law(&a, &b, &c), "a = {:?}\nb = {:?}\nc = {:?}", a, b, c);
+        }
+    }
+
+    #[test]
+    fn test_join_is_associative() {
+        check(|a, b, c| a.join(b).join(c) == a.join(&b.join(c)));
+    }
+
+    #[test]
+    fn test_join_is_commutative() {
+        check(|a, b, _| a.join(b) == b.join(a));
+    }
+
+    #[test]
+    fn test_join_is_idempotent() {
+        check(|a, _, _| a.join(a) == *a);
+    }
+
+    #[test]
+    fn test_bottom_is_identity_and_top_absorbs() {
+        check(|a, _, _| {
+            a.join(&AbstractValue::Bottom) == *a
+                && AbstractValue::Bottom.join(a) == *a
+                && a.join(&AbstractValue::Top) == AbstractValue::Top
+        });
+    }
+
+    #[test]
+    fn test_leq_is_a_partial_order() {
+        check(|a, b, c| {
+            let reflexive = a.leq(a);
+            let antisymmetric = !(a.leq(b) && b.leq(a)) || a == b;
+            let transitive = !(a.leq(b) && b.leq(c)) || a.leq(c);
+            let bounded = AbstractValue::Bottom.leq(a) && a.leq(&AbstractValue::Top);
+            reflexive && antisymmetric && transitive && bounded
+        });
+    }
+
+    #[test]
+    fn test_leq_agrees_with_join() {
+        check(|a, b, c| {
+            let joined = a.join(b);
+            let consistent = a.leq(b) == (joined == *b);
+            let upper_bound = a.leq(&joined) && b.leq(&joined);
+            // `c` above both is above their join
+            let least = !(a.leq(c) && b.leq(c)) || joined.leq(c);
+            consistent && upper_bound && least
+        });
+    }
+
+    #[test]
+    fn test_widen_is_an_upper_bound() {
+        check(|a, b, _| {
+            let widened = a.widen(b);
+            a.leq(&widened) && b.leq(&widened)
+        });
+    }
+
+    #[test]
+    fn test_narrow_stays_below() {
+        // even when `b` is not below `a`, e.g. an array nested deeper than the widened one
+        check(|a, b, _| {
+            let narrowed = a.narrow(b);
+            narrowed.leq(a) && (!b.leq(a) || b.leq(&narrowed))
+        });
+    }
+
+    #[test]
+    fn test_union_of_is_independent_of_order() {
+        check(|a, b, c| {
+            let orders = [
+                [a, b, c],
+                [a, c, b],
+                [b, a, c],
+                [b, c, a],
+                [c, a, b],
+                [c, b, a],
+            ];
+            let unions: Vec<_> = orders
+                .iter()
+                .map(|order| AbstractValue::union_of(order.map(Clone::clone)))
+                .collect();
+            unions.iter().all(|union| *union == unions[0])
+        });
+    }
+
+    #[test]
+    fn test_meet_is_the_greatest_lower_bound() {
+        check(|a, b, c| {
+            let met = a.meet(b);
+            let commutative = met == b.meet(a);
+            let consistent = a.leq(b) == (met == *a);
+            let lower_bound = met.leq(a) && met.leq(b);
+            // `c` below both is below their meet, and so is what `c` has in common with both
+            let greatest = (!(c.leq(a) && c.leq(b)) || c.leq(&met)) && c.meet(a).meet(b).leq(&met);
+            let bounded = a.meet(&AbstractValue::Top) == *a
+                && a.meet(&AbstractValue::Bottom) == AbstractValue::Bottom;
+            commutative && consistent && lower_bound && greatest && bounded
+        });
     }
 }
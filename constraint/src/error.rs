@@ -0,0 +1,123 @@
+use std::fmt;
+
+use crate::{Type, TypeVar};
+
+// location of a node in the source, as a half-open range of char offsets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Span { start, end }
+    }
+
+    // smallest span covering both `self` and `other`
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            start: usize::min(self.start, other.start),
+            end: usize::max(self.end, other.end),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeError {
+    UnboundVariable {
+        name: String,
+        span: Span,
+    },
+    Mismatch {
+        expected: Type,
+        found: Type,
+        span: Span,
+    },
+    InfiniteType {
+        var: TypeVar,
+        ty: Type,
+        span: Span,
+    },
+    NotAFunction {
+        found: Type,
+        span: Span,
+    },
+}
+
+impl TypeError {
+    pub fn span(&self) -> Span {
+        match self {
+            TypeError::UnboundVariable { span, .. }
+            | TypeError::Mismatch { span, .. }
+            | TypeError::InfiniteType { span, .. }
+            | TypeError::NotAFunction { span, .. } => *span,
+        }
+    }
+
+    // print the error along with the offending source line, underlining the span
+    //
+    // ```
+    // error: type mismatch: expected Bool, found Int
+    //  --> 1:4
+    //   |
+    // 1 | if 1 then 2 else 3
+    //   |    ^
+    // ```
+    pub fn render(&self, source: &str) -> String {
+        let span = self.span();
+        let (line, column) = line_column(source, span.start);
+        let text = source.lines().nth(line - 1).unwrap_or("");
+
+        // underline up to the end of the span, or the end of the line for multi-line spans
+        let line_len = text.chars().count();
+        let width = usize::min(span.end - span.start, line_len + 1 - column).max(1);
+
+        let gutter = " ".repeat(line.to_string().len());
+        format!(
+            "error: {}\n{}--> {}:{}\n{} |\n{} | {}\n{} | {}{}",
+            self,
+            gutter,
+            line,
+            column,
+            gutter,
+            line,
+            text,
+            gutter,
+            " ".repeat(column - 1),
+            "^".repeat(width),
+        )
+    }
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeError::UnboundVariable { name, .. } => write!(f, "unbound variable `{}`", name),
+            TypeError::Mismatch {
+                expected, found, ..
+            } => write!(f, "type mismatch: expected {}, found {}", expected, found),
+            TypeError::InfiniteType { var, ty, .. } => {
+                write!(f, "infinite type: t{} occurs in {}", var.0, ty)
+            }
+            TypeError::NotAFunction { found, .. } => {
+                write!(f, "expected a function, found {}", found)
+            }
+        }
+    }
+}
+
+// 1-based line and column of a char offset
+fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let mut line = 1;
+    let mut column = 1;
+    for c in source.chars().take(offset) {
+        if c == '\n' {
+            line += 1;
+            column = 1;
+        } else {
+            column += 1;
+        }
+    }
+    (line, column)
+}
@@ -0,0 +1,157 @@
+use std::fmt;
+
+use crate::{error::Span, parser::ParseError};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    Int(i32),
+    Ident(String),
+    Let,
+    In,
+    If,
+    Then,
+    Else,
+    True,
+    False,
+    Lambda, // `\` or `λ`
+    Dot,
+    Assign, // `=`
+    LParen,
+    RParen,
+    Op(String),
+    Eof,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenKind::Int(n) => write!(f, "integer `{}`", n),
+            TokenKind::Ident(name) => write!(f, "identifier `{}`", name),
+            TokenKind::Let => write!(f, "`let`"),
+            TokenKind::In => write!(f, "`in`"),
+            TokenKind::If => write!(f, "`if`"),
+            TokenKind::Then => write!(f, "`then`"),
+            TokenKind::Else => write!(f, "`else`"),
+            TokenKind::True => write!(f, "`true`"),
+            TokenKind::False => write!(f, "`false`"),
+            TokenKind::Lambda => write!(f, "`\\`"),
+            TokenKind::Dot => write!(f, "`.`"),
+            TokenKind::Assign => write!(f, "`=`"),
+            TokenKind::LParen => write!(f, "`(`"),
+            TokenKind::RParen => write!(f, "`)`"),
+            TokenKind::Op(op) => write!(f, "operator `{}`", op),
+            TokenKind::Eof => write!(f, "end of input"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub line: usize,
+    pub column: usize,
+    pub span: Span,
+}
+
+// split the source into tokens, tracking 1-based line/column of each token
+pub fn tokenize(source: &str) -> Result<Vec<Token>, ParseError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    let mut line = 1;
+    let mut column = 1;
+
+    while pos < chars.len() {
+        let c = chars[pos];
+        let (start_line, start_column) = (line, column);
+
+        if c == '\n' {
+            pos += 1;
+            line += 1;
+            column = 1;
+            continue;
+        }
+        if c.is_whitespace() {
+            pos += 1;
+            column += 1;
+            continue;
+        }
+        // `--` starts a comment running to the end of the line
+        if c == '-' && chars.get(pos + 1) == Some(&'-') {
+            while pos < chars.len() && chars[pos] != '\n' {
+                pos += 1;
+            }
+            continue;
+        }
+
+        let start = pos;
+        let kind = if c.is_ascii_digit() {
+            while pos < chars.len() && chars[pos].is_ascii_digit() {
+                pos += 1;
+            }
+            let text: String = chars[start..pos].iter().collect();
+            let n = text.parse::<i32>().map_err(|_| ParseError {
+                message: format!("integer literal `{}` is out of range", text),
+                line: start_line,
+                column: start_column,
+                expected: Vec::new(),
+            })?;
+            TokenKind::Int(n)
+        } else if c.is_alphabetic() || c == '_' {
+            while pos < chars.len() && (chars[pos].is_alphanumeric() || chars[pos] == '_') {
+                pos += 1;
+            }
+            let text: String = chars[start..pos].iter().collect();
+            match text.as_str() {
+                "let" => TokenKind::Let,
+                "in" => TokenKind::In,
+                "if" => TokenKind::If,
+                "then" => TokenKind::Then,
+                "else" => TokenKind::Else,
+                "true" => TokenKind::True,
+                "false" => TokenKind::False,
+                _ => TokenKind::Ident(text),
+            }
+        } else {
+            let next = chars.get(pos + 1).copied();
+            let (kind, len) = match (c, next) {
+                ('=', Some('=')) => (TokenKind::Op("==".to_string()), 2),
+                ('!', Some('=')) => (TokenKind::Op("!=".to_string()), 2),
+                ('<', Some('=')) => (TokenKind::Op("<=".to_string()), 2),
+                ('>', Some('=')) => (TokenKind::Op(">=".to_string()), 2),
+                ('=', _) => (TokenKind::Assign, 1),
+                ('\\', _) | ('λ', _) => (TokenKind::Lambda, 1),
+                ('.', _) => (TokenKind::Dot, 1),
+                ('(', _) => (TokenKind::LParen, 1),
+                (')', _) => (TokenKind::RParen, 1),
+                ('+' | '-' | '*' | '/' | '<' | '>', _) => (TokenKind::Op(c.to_string()), 1),
+                _ => {
+                    return Err(ParseError {
+                        message: format!("unexpected character `{}`", c),
+                        line: start_line,
+                        column: start_column,
+                        expected: Vec::new(),
+                    })
+                }
+            };
+            pos += len;
+            kind
+        };
+
+        column += pos - start;
+        tokens.push(Token {
+            kind,
+            line: start_line,
+            column: start_column,
+            span: Span::new(start, pos),
+        });
+    }
+
+    tokens.push(Token {
+        kind: TokenKind::Eof,
+        line,
+        column,
+        span: Span::new(pos, pos),
+    });
+    Ok(tokens)
+}
@@ -1,4 +1,14 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use error::{Span, TypeError};
+use parser::parse;
+
+mod error;
+mod lexer;
+mod parser;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 enum Type {
@@ -11,9 +21,41 @@ enum Type {
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct TypeVar(usize);
 
-// AST node
-#[derive(Debug)]
-enum Expr {
+// polymorphic type scheme: ∀ vars. ty
+//
+// `let`-bound values are generalized into a scheme, and every use of the
+// variable instantiates the quantified vars with fresh type variables.
+// lambda params are stored as monomorphic schemes (no quantified vars).
+#[derive(Debug, Clone)]
+struct Scheme {
+    vars: Vec<TypeVar>,
+    ty: Type,
+}
+
+impl Scheme {
+    fn mono(ty: Type) -> Self {
+        Scheme {
+            vars: Vec::new(),
+            ty,
+        }
+    }
+}
+
+// AST node, annotated with the span of source it was parsed from
+#[derive(Debug, PartialEq)]
+struct Expr {
+    kind: ExprKind,
+    span: Span,
+}
+
+impl Expr {
+    fn new(kind: ExprKind, span: Span) -> Self {
+        Expr { kind, span }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum ExprKind {
     IntLiteral(i32),
     BoolLiteral(bool),
     Variable(String),
@@ -41,7 +83,7 @@ enum Expr {
 struct TypeContext {
     next_var_id: usize,
     substitutions: HashMap<TypeVar, Type>,
-    env: HashMap<String, Type>,
+    env: HashMap<String, Scheme>,
 }
 
 impl TypeContext {
@@ -53,11 +95,48 @@ impl TypeContext {
         }
     }
 
-    // create a new type variable
-    fn new_type_var(&mut self) -> Type {
+    // context with the built-in infix operators bound in the environment
+    fn with_operators() -> Self {
+        let mut ctx = TypeContext::new();
+
+        let binary = |operand: Type, result: Type| {
+            Type::Func(
+                Box::new(operand.clone()),
+                Box::new(Type::Func(Box::new(operand), Box::new(result))),
+            )
+        };
+        for op in ["+", "-", "*", "/"] {
+            ctx.env
+                .insert(op.to_string(), Scheme::mono(binary(Type::Int, Type::Int)));
+        }
+        for op in ["<", "<=", ">", ">="] {
+            ctx.env
+                .insert(op.to_string(), Scheme::mono(binary(Type::Int, Type::Bool)));
+        }
+        // equality works on any type: ∀a. a -> a -> Bool
+        for op in ["==", "!="] {
+            let tv = ctx.fresh_var();
+            ctx.env.insert(
+                op.to_string(),
+                Scheme {
+                    ty: binary(Type::Var(tv.clone()), Type::Bool),
+                    vars: vec![tv],
+                },
+            );
+        }
+
+        ctx
+    }
+
+    fn fresh_var(&mut self) -> TypeVar {
         let var = TypeVar(self.next_var_id);
         self.next_var_id += 1;
-        Type::Var(var)
+        var
+    }
+
+    // create a new type variable
+    fn new_type_var(&mut self) -> Type {
+        Type::Var(self.fresh_var())
     }
 
     // find type variable's real type
@@ -77,10 +156,11 @@ impl TypeContext {
         }
     }
 
-    // unifying two types and resolve constraint
-    fn unify(&mut self, t1: &Type, t2: &Type) -> Result<(), String> {
-        let a = self.lookup_type(t1);
-        let b = self.lookup_type(t2);
+    // unifying two types and resolve constraint.
+    // errors are reported against `span`, the expression the constraint came from.
+    fn unify(&mut self, expected: &Type, found: &Type, span: Span) -> Result<(), TypeError> {
+        let a = self.lookup_type(expected);
+        let b = self.lookup_type(found);
 
         match (&a, &b) {
             (&Type::Int, &Type::Int) | (&Type::Bool, &Type::Bool) => Ok(()),
@@ -89,81 +169,190 @@ impl TypeContext {
                 if t == Type::Var(tv.clone()) {
                     Ok(())
                 } else if occurs_check(tv, &t, self) {
-                    Err(format!("Occurs check failed for {:?} in {:?}", tv, t))
+                    Err(TypeError::InfiniteType {
+                        var: tv.clone(),
+                        ty: apply_substitutions(&t, self),
+                        span,
+                    })
                 } else {
                     self.substitutions.insert(tv.clone(), t);
                     Ok(())
                 }
             }
-            (&Type::Func(ref a1, ref a2), &Type::Func(ref b1, ref b2)) => {
-                self.unify(&*a1, &*b1)?;
-                self.unify(&*a2, &*b2)
+            (Type::Func(a1, a2), Type::Func(b1, b2)) => {
+                self.unify(a1, b1, span)?;
+                self.unify(a2, b2, span)
             }
-            _ => Err(format!("Type mismatch: {:?} vs {:?}", a, b)),
+            _ => Err(TypeError::Mismatch {
+                expected: apply_substitutions(&a, self),
+                found: apply_substitutions(&b, self),
+                span,
+            }),
         }
     }
+
+    // bind `name` in the environment, returning the shadowed scheme (if any)
+    // so that the caller can restore it when leaving the scope
+    fn bind(&mut self, name: &str, scheme: Scheme) -> Option<Scheme> {
+        self.env.insert(name.to_string(), scheme)
+    }
+
+    fn unbind(&mut self, name: &str, previous: Option<Scheme>) {
+        match previous {
+            Some(scheme) => self.env.insert(name.to_string(), scheme),
+            None => self.env.remove(name),
+        };
+    }
+
+    // replace quantified vars of the scheme with fresh type variables
+    fn instantiate(&mut self, scheme: &Scheme) -> Type {
+        let mapping: HashMap<TypeVar, Type> = scheme
+            .vars
+            .iter()
+            .map(|tv| (tv.clone(), self.new_type_var()))
+            .collect();
+        substitute(&scheme.ty, &mapping)
+    }
+
+    // quantify over the type variables of `ty` that are not free in the environment
+    fn generalize(&mut self, ty: &Type) -> Scheme {
+        let ty = apply_substitutions(ty, self);
+
+        let mut env_vars = HashSet::new();
+        let env_schemes: Vec<Scheme> = self.env.values().cloned().collect();
+        for scheme in &env_schemes {
+            let resolved = apply_substitutions(&scheme.ty, self);
+            let mut vars = HashSet::new();
+            free_type_vars(&resolved, &mut vars);
+            for tv in &scheme.vars {
+                vars.remove(tv);
+            }
+            env_vars.extend(vars);
+        }
+
+        let mut ty_vars = Vec::new();
+        collect_type_vars(&ty, &mut ty_vars);
+        let vars = ty_vars
+            .into_iter()
+            .filter(|tv| !env_vars.contains(tv))
+            .collect();
+
+        Scheme { vars, ty }
+    }
 }
 
-fn occurs_check(var: &TypeVar, ty: &Type, ctx: &mut TypeContext) -> bool {
+// collect type variables in order of appearance, without duplicates
+fn collect_type_vars(ty: &Type, vars: &mut Vec<TypeVar>) {
+    match ty {
+        Type::Var(tv) if !vars.contains(tv) => vars.push(tv.clone()),
+        Type::Func(t1, t2) => {
+            collect_type_vars(t1, vars);
+            collect_type_vars(t2, vars);
+        }
+        _ => {}
+    }
+}
+
+fn free_type_vars(ty: &Type, vars: &mut HashSet<TypeVar>) {
     match ty {
         Type::Var(tv) => {
+            vars.insert(tv.clone());
+        }
+        Type::Func(t1, t2) => {
+            free_type_vars(t1, vars);
+            free_type_vars(t2, vars);
+        }
+        _ => {}
+    }
+}
+
+// replace type variables according to `mapping`, leaving other vars untouched
+fn substitute(ty: &Type, mapping: &HashMap<TypeVar, Type>) -> Type {
+    match ty {
+        Type::Var(tv) => mapping.get(tv).cloned().unwrap_or_else(|| ty.clone()),
+        Type::Func(t1, t2) => Type::Func(
+            Box::new(substitute(t1, mapping)),
+            Box::new(substitute(t2, mapping)),
+        ),
+        _ => ty.clone(),
+    }
+}
+
+fn occurs_check(var: &TypeVar, ty: &Type, ctx: &mut TypeContext) -> bool {
+    match ty {
+        Type::Var(_) => {
             let t = ctx.lookup_type(ty);
             match t {
-                Type::Var(tv2) => tv == &tv2,
+                Type::Var(tv) => var == &tv,
                 _ => occurs_check(var, &t, ctx),
             }
         }
-        Type::Func(t1, t2) => occurs_check(var, &t1, ctx) || occurs_check(var, &t2, ctx),
+        Type::Func(t1, t2) => occurs_check(var, t1, ctx) || occurs_check(var, t2, ctx),
         _ => false,
     }
 }
 
-fn infer(expr: &Expr, ctx: &mut TypeContext) -> Result<Type, String> {
-    match expr {
-        Expr::IntLiteral(_) => Ok(Type::Int),
-        Expr::BoolLiteral(_) => Ok(Type::Bool),
-        Expr::Variable(name) => {
-            if let Some(ty) = ctx.env.get(name) {
-                Ok(ty.clone())
+fn infer(expr: &Expr, ctx: &mut TypeContext) -> Result<Type, TypeError> {
+    match &expr.kind {
+        ExprKind::IntLiteral(_) => Ok(Type::Int),
+        ExprKind::BoolLiteral(_) => Ok(Type::Bool),
+        ExprKind::Variable(name) => {
+            if let Some(scheme) = ctx.env.get(name).cloned() {
+                Ok(ctx.instantiate(&scheme))
             } else {
-                Err(format!("Unbound variable: {}", name))
+                Err(TypeError::UnboundVariable {
+                    name: name.clone(),
+                    span: expr.span,
+                })
             }
         }
-        Expr::Lambda { param, body } => {
+        ExprKind::Lambda { param, body } => {
             let param_type = ctx.new_type_var();
-            ctx.env.insert(param.clone(), param_type.clone());
-            let body_type = infer(body, ctx)?;
-            ctx.env.remove(param);
+            let previous = ctx.bind(param, Scheme::mono(param_type.clone()));
+            let body_type = infer(body, ctx);
+            ctx.unbind(param, previous);
+            let body_type = body_type?;
             Ok(Type::Func(Box::new(param_type), Box::new(body_type)))
         }
-        Expr::Application { func, arg } => {
+        ExprKind::Application { func, arg } => {
             let func_type = infer(func, ctx)?;
+            let resolved = ctx.lookup_type(&func_type);
+            if !matches!(resolved, Type::Var(_) | Type::Func(..)) {
+                return Err(TypeError::NotAFunction {
+                    found: apply_substitutions(&resolved, ctx),
+                    span: func.span,
+                });
+            }
             let arg_type = infer(arg, ctx)?;
             let result_type = ctx.new_type_var();
+            // the function's parameter type is what the argument is expected to be
             ctx.unify(
                 &func_type,
                 &Type::Func(Box::new(arg_type), Box::new(result_type.clone())),
+                arg.span,
             )?;
             Ok(result_type)
         }
-        Expr::If {
+        ExprKind::If {
             cond,
             then_branch,
             else_branch,
         } => {
             let cond_type = infer(cond, ctx)?;
-            ctx.unify(&cond_type, &Type::Bool)?;
+            ctx.unify(&Type::Bool, &cond_type, cond.span)?;
             let then_type = infer(then_branch, ctx)?;
             let else_type = infer(else_branch, ctx)?;
-            ctx.unify(&then_type, &else_type)?;
+            ctx.unify(&then_type, &else_type, else_branch.span)?;
             Ok(then_type)
         }
-        Expr::Let { name, value, body } => {
+        ExprKind::Let { name, value, body } => {
             let value_type = infer(value, ctx)?;
-            ctx.env.insert(name.clone(), value_type);
-            let body_type = infer(body, ctx)?;
-            ctx.env.remove(name);
-            Ok(body_type)
+            // generalize the value's type so that the body can use it polymorphically
+            let scheme = ctx.generalize(&value_type);
+            let previous = ctx.bind(name, scheme);
+            let body_type = infer(body, ctx);
+            ctx.unbind(name, previous);
+            body_type
         } // 다른 표현식에 대한 처리...
     }
 }
@@ -171,7 +360,10 @@ fn infer(expr: &Expr, ctx: &mut TypeContext) -> Result<Type, String> {
 // apply substitutions to get the actual type
 fn apply_substitutions(ty: &Type, ctx: &mut TypeContext) -> Type {
     match ty {
-        Type::Var(_) => ctx.lookup_type(ty),
+        Type::Var(_) => match ctx.lookup_type(ty) {
+            resolved @ Type::Var(_) => resolved,
+            resolved => apply_substitutions(&resolved, ctx),
+        },
         Type::Func(t1, t2) => Type::Func(
             Box::new(apply_substitutions(t1, ctx)),
             Box::new(apply_substitutions(t2, ctx)),
@@ -182,69 +374,162 @@ fn apply_substitutions(ty: &Type, ctx: &mut TypeContext) -> Type {
 
 // convert type to string for output
 fn type_to_string(ty: &Type, ctx: &mut TypeContext) -> String {
-    match ty {
-        Type::Int => "Int".to_string(),
-        Type::Bool => "Bool".to_string(),
-        Type::Var(tv) => {
-            let actual_type = ctx.lookup_type(&Type::Var(tv.clone()));
-            if let Type::Var(_) = actual_type {
-                format!("t{}", tv.0)
-            } else {
-                type_to_string(&actual_type, ctx)
+    apply_substitutions(ty, ctx).to_string()
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Int => write!(f, "Int"),
+            Type::Bool => write!(f, "Bool"),
+            Type::Var(tv) => write!(f, "t{}", tv.0),
+            Type::Func(t1, t2) => write!(f, "({} -> {})", t1, t2),
+        }
+    }
+}
+
+// fully parenthesized form of the expression, with infix operators in prefix position
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ExprKind::IntLiteral(n) => write!(f, "{}", n),
+            ExprKind::BoolLiteral(b) => write!(f, "{}", b),
+            ExprKind::Variable(name) => write!(f, "{}", name),
+            ExprKind::Lambda { param, body } => write!(f, "(\\{}. {})", param, body),
+            ExprKind::Application { func, arg } => write!(f, "({} {})", func, arg),
+            ExprKind::Let { name, value, body } => {
+                write!(f, "(let {} = {} in {})", name, value, body)
             }
+            ExprKind::If {
+                cond,
+                then_branch,
+                else_branch,
+            } => write!(f, "(if {} then {} else {})", cond, then_branch, else_branch),
         }
-        Type::Func(t1, t2) => format!(
-            "({} -> {})",
-            type_to_string(t1, ctx),
-            type_to_string(t2, ctx)
-        ),
     }
 }
 
 fn main() {
-    let mut ctx = TypeContext::new();
-
-    // assume '+' operator as a function and add to environment
-    ctx.env.insert(
-        "+".to_string(),
-        Type::Func(
-            Box::new(Type::Int),
-            Box::new(Type::Func(Box::new(Type::Int), Box::new(Type::Int))),
-        ),
-    );
-
-    // let add = λx.λy.x + y => add 1 2
-    let expr = Expr::Let {
-        name: "add".to_string(),
-        value: Box::new(Expr::Lambda {
-            param: "x".to_string(),
-            body: Box::new(Expr::Lambda {
-                param: "y".to_string(),
-                body: Box::new(Expr::Application {
-                    func: Box::new(Expr::Application {
-                        func: Box::new(Expr::Variable("+".to_string())),
-                        arg: Box::new(Expr::Variable("x".to_string())),
-                    }),
-                    arg: Box::new(Expr::Variable("y".to_string())),
-                }),
-            }),
-        }),
-        body: Box::new(Expr::Application {
-            func: Box::new(Expr::Application {
-                func: Box::new(Expr::Variable("add".to_string())),
-                arg: Box::new(Expr::IntLiteral(1)),
-            }),
-            arg: Box::new(Expr::IntLiteral(2)),
-        }),
-    };
+    let programs = [
+        "let add = \\x. \\y. x + y in add 1 2",
+        "let id = \\x. x in if id true then id 1 else 2",
+        "let add = \\x. \\y. x + y in add 1 true",
+    ];
 
-    match infer(&expr, &mut ctx) {
-        Ok(ty) => {
-            let final_type = apply_substitutions(&ty, &mut ctx);
-            println!("Expression Type: {}", type_to_string(&final_type, &mut ctx));
-        }
-        Err(err) => {
-            println!("Type inference error: {}", err);
+    for source in programs {
+        let mut ctx = TypeContext::with_operators();
+        let expr = match parse(source) {
+            Ok(expr) => expr,
+            Err(err) => {
+                println!("Parse error: {}", err);
+                continue;
+            }
+        };
+
+        match infer(&expr, &mut ctx) {
+            Ok(ty) => {
+                let final_type = apply_substitutions(&ty, &mut ctx);
+                println!("Expression Type: {}", type_to_string(&final_type, &mut ctx));
+            }
+            Err(err) => {
+                println!("{}", err.render(source));
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod inference_tests {
+    use super::*;
+
+    fn infer_to_string(source: &str) -> Result<String, String> {
+        let expr = parse(source).map_err(|err| err.to_string())?;
+        let mut ctx = TypeContext::with_operators();
+        let ty = infer(&expr, &mut ctx).map_err(|err| err.to_string())?;
+        let final_type = apply_substitutions(&ty, &mut ctx);
+        Ok(type_to_string(&final_type, &mut ctx))
+    }
+
+    #[test]
+    fn test_let_polymorphism() {
+        let source = "let id = \\x. x in if id true then id 1 else 2";
+        assert_eq!(infer_to_string(source), Ok("Int".to_string()));
+    }
+
+    #[test]
+    fn test_lambda_bound_variable_is_monomorphic() {
+        let source = "(\\id. if id true then id 1 else 2) (\\x. x)";
+        assert!(infer_to_string(source).is_err());
+    }
+
+    #[test]
+    fn test_generalization_skips_env_vars() {
+        // `y`'s type var is free in the environment, so `f` must not quantify over it
+        let source = "\\y. let f = \\x. y in if f 1 then f true else y";
+        assert_eq!(infer_to_string(source), Ok("(Bool -> Bool)".to_string()));
+    }
+
+    #[test]
+    fn test_polymorphic_equality() {
+        let source = "if 1 == 2 then true == false else 1 < 2";
+        assert_eq!(infer_to_string(source), Ok("Bool".to_string()));
+    }
+
+    fn infer_error(source: &str) -> TypeError {
+        let expr = parse(source).unwrap();
+        let mut ctx = TypeContext::with_operators();
+        infer(&expr, &mut ctx).unwrap_err()
+    }
+
+    #[test]
+    fn test_unbound_variable_error() {
+        let err = infer_error("let x = 1 in y + x");
+        assert_eq!(
+            err,
+            TypeError::UnboundVariable {
+                name: "y".to_string(),
+                span: Span::new(13, 14),
+            }
+        );
+    }
+
+    #[test]
+    fn test_mismatch_error_points_at_argument() {
+        let source = "let add = \\x. \\y. x + y in add 1 true";
+        let err = infer_error(source);
+        assert_eq!(
+            err,
+            TypeError::Mismatch {
+                expected: Type::Int,
+                found: Type::Bool,
+                span: Span::new(33, 37),
+            }
+        );
+        assert_eq!(
+            err.render(source),
+            [
+                "error: type mismatch: expected Int, found Bool",
+                " --> 1:34",
+                "  |",
+                "1 | let add = \\x. \\y. x + y in add 1 true",
+                "  |                                  ^^^^",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_infinite_type_and_not_a_function_errors() {
+        assert!(matches!(
+            infer_error("\\x. x x"),
+            TypeError::InfiniteType { .. }
+        ));
+        assert_eq!(
+            infer_error("if true then 1 2 else 3"),
+            TypeError::NotAFunction {
+                found: Type::Int,
+                span: Span::new(13, 14),
+            }
+        );
+    }
+}
@@ -0,0 +1,320 @@
+use std::fmt;
+
+use crate::{
+    error::Span,
+    lexer::{tokenize, Token, TokenKind},
+    Expr, ExprKind,
+};
+
+// Recursive-descent parser for the surface syntax of the lambda language.
+//
+// Grammar (lowest to highest precedence):
+//
+// ```
+// expr       := "let" IDENT "=" expr "in" expr
+//             | "\" IDENT "." expr
+//             | "if" expr "then" expr "else" expr
+//             | comparison
+// comparison := additive (("==" | "!=" | "<" | "<=" | ">" | ">=") additive)?
+// additive   := term (("+" | "-") term)*
+// term       := application (("*" | "/") application)*
+// application:= atom atom*
+// atom       := INT | "true" | "false" | IDENT | "(" expr ")"
+// ```
+//
+// Infix operators are desugared into curried applications of a variable
+// named after the operator, so `x + y` becomes `((+) x) y`.
+//
+// Every node carries the span of the source it was parsed from.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+    pub expected: Vec<String>,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)?;
+        if !self.expected.is_empty() {
+            write!(f, " (expected {})", self.expected.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+pub fn parse(source: &str) -> Result<Expr, ParseError> {
+    let tokens = tokenize(source)?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let expr = parser.parse_expr()?;
+    parser.expect(TokenKind::Eof)?;
+    Ok(expr)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    // span from the start of the token at `start` up to the last consumed token
+    fn span_from(&self, start: usize) -> Span {
+        let end = self.tokens[self.pos.saturating_sub(1).max(start)].span;
+        self.tokens[start].span.to(end)
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos]
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.pos].clone();
+        if token.kind != TokenKind::Eof {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn error(&self, expected: &[&str]) -> ParseError {
+        let token = self.peek();
+        ParseError {
+            message: format!("unexpected {}", token.kind),
+            line: token.line,
+            column: token.column,
+            expected: expected.iter().map(|e| e.to_string()).collect(),
+        }
+    }
+
+    fn expect(&mut self, kind: TokenKind) -> Result<Token, ParseError> {
+        if self.peek().kind == kind {
+            Ok(self.advance())
+        } else {
+            Err(self.error(&[&kind.to_string()]))
+        }
+    }
+
+    fn expect_ident(&mut self) -> Result<String, ParseError> {
+        match &self.peek().kind {
+            TokenKind::Ident(name) => {
+                let name = name.clone();
+                self.advance();
+                Ok(name)
+            }
+            _ => Err(self.error(&["identifier"])),
+        }
+    }
+
+    fn parse_expr(&mut self) -> Result<Expr, ParseError> {
+        let start = self.pos;
+        let kind = match self.peek().kind {
+            TokenKind::Let => {
+                self.advance();
+                let name = self.expect_ident()?;
+                self.expect(TokenKind::Assign)?;
+                let value = self.parse_expr()?;
+                self.expect(TokenKind::In)?;
+                let body = self.parse_expr()?;
+                ExprKind::Let {
+                    name,
+                    value: Box::new(value),
+                    body: Box::new(body),
+                }
+            }
+            TokenKind::Lambda => {
+                self.advance();
+                let param = self.expect_ident()?;
+                self.expect(TokenKind::Dot)?;
+                let body = self.parse_expr()?;
+                ExprKind::Lambda {
+                    param,
+                    body: Box::new(body),
+                }
+            }
+            TokenKind::If => {
+                self.advance();
+                let cond = self.parse_expr()?;
+                self.expect(TokenKind::Then)?;
+                let then_branch = self.parse_expr()?;
+                self.expect(TokenKind::Else)?;
+                let else_branch = self.parse_expr()?;
+                ExprKind::If {
+                    cond: Box::new(cond),
+                    then_branch: Box::new(then_branch),
+                    else_branch: Box::new(else_branch),
+                }
+            }
+            _ => return self.parse_comparison(),
+        };
+        Ok(Expr::new(kind, self.span_from(start)))
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, ParseError> {
+        let left = self.parse_additive()?;
+        if let Some(op) = self.match_op(&["==", "!=", "<", "<=", ">", ">="]) {
+            let right = self.parse_additive()?;
+            return Ok(binary(op, left, right));
+        }
+        Ok(left)
+    }
+
+    fn parse_additive(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.parse_term()?;
+        while let Some(op) = self.match_op(&["+", "-"]) {
+            let right = self.parse_term()?;
+            left = binary(op, left, right);
+        }
+        Ok(left)
+    }
+
+    fn parse_term(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.parse_application()?;
+        while let Some(op) = self.match_op(&["*", "/"]) {
+            let right = self.parse_application()?;
+            left = binary(op, left, right);
+        }
+        Ok(left)
+    }
+
+    fn parse_application(&mut self) -> Result<Expr, ParseError> {
+        let mut func = self.parse_atom()?;
+        while self.starts_atom() {
+            let arg = self.parse_atom()?;
+            let span = func.span.to(arg.span);
+            func = Expr::new(
+                ExprKind::Application {
+                    func: Box::new(func),
+                    arg: Box::new(arg),
+                },
+                span,
+            );
+        }
+        Ok(func)
+    }
+
+    fn parse_atom(&mut self) -> Result<Expr, ParseError> {
+        let token = self.peek().clone();
+        let kind = match token.kind {
+            TokenKind::Int(n) => ExprKind::IntLiteral(n),
+            TokenKind::True => ExprKind::BoolLiteral(true),
+            TokenKind::False => ExprKind::BoolLiteral(false),
+            TokenKind::Ident(name) => ExprKind::Variable(name),
+            TokenKind::LParen => {
+                self.advance();
+                let mut expr = self.parse_expr()?;
+                let close = self.expect(TokenKind::RParen)?;
+                // include the parentheses so that errors underline the whole group
+                expr.span = token.span.to(close.span);
+                return Ok(expr);
+            }
+            _ => return Err(self.error(&["expression"])),
+        };
+        self.advance();
+        Ok(Expr::new(kind, token.span))
+    }
+
+    fn starts_atom(&self) -> bool {
+        matches!(
+            self.peek().kind,
+            TokenKind::Int(_)
+                | TokenKind::True
+                | TokenKind::False
+                | TokenKind::Ident(_)
+                | TokenKind::LParen
+        )
+    }
+
+    fn match_op(&mut self, ops: &[&str]) -> Option<Token> {
+        match &self.peek().kind {
+            TokenKind::Op(op) if ops.contains(&op.as_str()) => Some(self.advance()),
+            _ => None,
+        }
+    }
+}
+
+// `left op right` => `((op) left) right`
+fn binary(op: Token, left: Expr, right: Expr) -> Expr {
+    let TokenKind::Op(name) = op.kind else {
+        unreachable!("binary operators are lexed as `TokenKind::Op`")
+    };
+    let partial_span = left.span.to(op.span);
+    let span = partial_span.to(right.span);
+    Expr::new(
+        ExprKind::Application {
+            func: Box::new(Expr::new(
+                ExprKind::Application {
+                    func: Box::new(Expr::new(ExprKind::Variable(name), op.span)),
+                    arg: Box::new(left),
+                },
+                partial_span,
+            )),
+            arg: Box::new(right),
+        },
+        span,
+    )
+}
+
+#[cfg(test)]
+mod parser_tests {
+    use super::*;
+
+    fn parse_to_string(source: &str) -> String {
+        parse(source).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_parse_let_lambda_and_infix() {
+        assert_eq!(
+            parse_to_string("let add = \\x. \\y. x + y in add 1 2"),
+            "(let add = (\\x. (\\y. ((+ x) y))) in ((add 1) 2))"
+        );
+    }
+
+    #[test]
+    fn test_parse_operator_precedence() {
+        assert_eq!(
+            parse_to_string("1 + 2 * 3 == 7"),
+            "((== ((+ 1) ((* 2) 3))) 7)"
+        );
+    }
+
+    #[test]
+    fn test_parse_if() {
+        assert_eq!(
+            parse_to_string("if c then a else b"),
+            "(if c then a else b)"
+        );
+    }
+
+    #[test]
+    fn test_parse_spans() {
+        let source = "let f = \\x. x in (f 1) + 2";
+        let expr = parse(source).unwrap();
+        assert_eq!(expr.span, Span::new(0, source.len()));
+
+        let ExprKind::Let { value, body, .. } = &expr.kind else {
+            panic!("expected a let expression");
+        };
+        assert_eq!(value.span, Span::new(8, 13));
+        // `(f 1) + 2` => `((+ (f 1)) 2)`
+        let ExprKind::Application { func, .. } = &body.kind else {
+            panic!("expected an application");
+        };
+        let ExprKind::Application { arg: left, .. } = &func.kind else {
+            panic!("expected an application");
+        };
+        assert_eq!(left.span, Span::new(17, 22));
+    }
+
+    #[test]
+    fn test_parse_error_location() {
+        let err = parse("let x = 1\nin if x then").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 13));
+        assert_eq!(err.expected, vec!["expression".to_string()]);
+
+        let err = parse("let = 1 in x").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 5));
+        assert_eq!(err.expected, vec!["identifier".to_string()]);
+    }
+}